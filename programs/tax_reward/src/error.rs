@@ -14,25 +14,67 @@ pub enum TaxRewardError {
 
     #[msg("Calculation Overflow")]
     Overflow,
-    
+
     #[msg("Slippage Exceeded")]
     SlippageExceeded,
-    
+
     #[msg("Invalid Tax Rate - must be <= 10000 bps (100%)")]
     InvalidTaxRate,
-    
+
     #[msg("Invalid Token Account - wrong mint or authority")]
     InvalidTokenAccount,
-    
+
     #[msg("Reward Vault Insufficient Balance")]
     InsufficientRewardVault,
-    
+
     #[msg("DEX Swap Failed")]
     SwapFailed,
-    
+
     #[msg("Program is Paused")]
     ProgramPaused,
-    
+
     #[msg("Invalid Mint Supply")]
     InvalidMintSupply,
+
+    #[msg("Invalid Tax Window - must end in the future with start < end")]
+    InvalidTaxWindow,
+
+    #[msg("Too Many Tax Windows")]
+    TooManyTaxWindows,
+
+    #[msg("Invalid NFT Discount - NFT not held or not in the verified collection")]
+    InvalidNftDiscount,
+
+    #[msg("Wallet is excluded from rewards")]
+    RewardExcluded,
+
+    #[msg("Invalid Epoch - leaf count must be between 1 and the maximum")]
+    InvalidEpoch,
+
+    #[msg("Invalid Merkle Proof")]
+    InvalidMerkleProof,
+
+    #[msg("Epoch reward already claimed")]
+    AlreadyClaimed,
+
+    #[msg("Claim destination does not match the user's setting")]
+    InvalidClaimDestination,
+
+    #[msg("Rewards have not expired - holder interacted within the expiry window")]
+    RewardsNotExpired,
+
+    #[msg("Staking mode is disabled")]
+    StakingDisabled,
+
+    #[msg("Account still holds staked tokens")]
+    StakeNotEmpty,
+
+    #[msg("Invalid Lock Tier - tiers need a positive duration and a multiplier of at least 1x, penalty <= 10000 bps")]
+    InvalidLockTier,
+
+    #[msg("Reward vault cannot pay without dropping below its rent-exempt minimum")]
+    RewardVaultBelowRentFloor,
+
+    #[msg("Memo Too Long")]
+    MemoTooLong,
 }
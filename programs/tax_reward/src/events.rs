@@ -0,0 +1,30 @@
+//! Events emitted for off-chain indexers
+use anchor_lang::prelude::*;
+
+/// Result of a permissionless `audit_solvency` run
+#[event]
+pub struct SolvencyAudited {
+    pub mint: Pubkey,
+    /// Reward vault lamports above its rent-exempt minimum
+    pub available_lamports: u64,
+    pub outstanding_liability: u64,
+    /// `outstanding_liability - available_lamports`, or 0 when solvent
+    pub shortfall: u64,
+    /// True if this audit paused the program
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+/// Longest memo accepted by `donate_rewards`, in bytes
+pub const MAX_DONATION_MEMO_LEN: usize = 128;
+
+/// SOL added to the holder reward pool outside of taxed swaps
+#[event]
+pub struct RewardsDonated {
+    pub mint: Pubkey,
+    pub donor: Pubkey,
+    pub lamports: u64,
+    /// Free-form attribution, e.g. a partner or campaign name
+    pub memo: Option<String>,
+    pub timestamp: i64,
+}
@@ -1,8 +1,8 @@
 // Instruction context definitions using Anchor
 
+use crate::state::{Config, Epoch, GlobalState, LockPosition, TaxSchedule, UserInfo};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{TokenAccount, Token, Mint};
-use crate::state::{Config, GlobalState, UserInfo};
+use anchor_spl::token::{Mint, Token, TokenAccount};
 
 #[derive(Accounts)]
 #[instruction(tax_rate_bps: u16, dex_program: Pubkey)]
@@ -26,6 +26,14 @@ pub struct Initialize<'info> {
         bump
     )]
     pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init,
+        payer = authority,
+        space = TaxSchedule::LEN + 8,
+        seeds = [b"tax_schedule", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub tax_schedule: Account<'info, TaxSchedule>,
     #[account(
         init,
         payer = authority,
@@ -61,6 +69,9 @@ pub struct TaxedSwap<'info> {
     pub config: Account<'info, Config>,
     #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
     pub global_state: Account<'info, GlobalState>,
+    /// Omitted on deployments that have not published a schedule yet
+    #[account(seeds = [b"tax_schedule", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub tax_schedule: Option<Account<'info, TaxSchedule>>,
     #[account(mut, seeds = [b"token_vault", program_id.as_ref(), mint.key().as_ref()], bump, token::authority = vault_authority)]
     pub token_vault: Account<'info, TokenAccount>,
     /// CHECK: PDA used as token vault authority
@@ -85,6 +96,10 @@ pub struct TaxedSwap<'info> {
     pub mint: Account<'info, Mint>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
+    /// Partner NFT held by the user, for the collection discount
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+    /// CHECK: Metaplex metadata of the NFT mint, verified in the handler
+    pub nft_metadata: Option<AccountInfo<'info>>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
@@ -100,14 +115,367 @@ pub struct Claim<'info> {
     pub reward_vault: AccountInfo<'info>,
     #[account(mut, seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
     pub user_info: Account<'info, UserInfo>,
+    /// CHECK: holder being claimed for; must be the caller unless a delegate signs
+    pub user_wallet: AccountInfo<'info>,
+    /// CHECK: checked against UserInfo::claim_destination in the handler
     #[account(mut)]
+    pub claim_destination: AccountInfo<'info>,
+    /// The holder or their claim delegate
+    pub caller: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetPendingRewards<'info> {
+    #[account(seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
+    pub user_info: Account<'info, UserInfo>,
+    /// CHECK: holder being queried; only used to derive `user_info`
+    pub user_wallet: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimSettings<'info> {
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
+    pub user_info: Account<'info, UserInfo>,
     pub user_wallet: Signer<'info>,
     pub mint: Account<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = user_wallet,
+        space = UserInfo::LEN + 8,
+        seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+    #[account(
+        init_if_needed,
+        payer = user_wallet,
+        token::mint = mint,
+        token::authority = vault_authority,
+        seeds = [b"stake_vault", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    /// CHECK: PDA used as token vault authority
+    #[account(
+        seeds = [b"vault_authority", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
     #[account(mut)]
+    pub user_wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = user_wallet)]
     pub user_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
+    pub user_info: Account<'info, UserInfo>,
+    #[account(
+        mut,
+        seeds = [b"stake_vault", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        token::authority = vault_authority
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    /// CHECK: PDA used as token vault authority
+    #[account(
+        seeds = [b"vault_authority", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(lock_id: u64)]
+pub struct LockTokens<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = user_wallet,
+        space = UserInfo::LEN + 8,
+        seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+    #[account(
+        init,
+        payer = user_wallet,
+        space = LockPosition::LEN + 8,
+        seeds = [
+            b"lock",
+            program_id.as_ref(),
+            user_wallet.key().as_ref(),
+            mint.key().as_ref(),
+            &lock_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub lock_position: Account<'info, LockPosition>,
+    #[account(
+        init_if_needed,
+        payer = user_wallet,
+        token::mint = mint,
+        token::authority = vault_authority,
+        seeds = [b"stake_vault", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    /// CHECK: PDA used as token vault authority
+    #[account(
+        seeds = [b"vault_authority", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(lock_id: u64)]
+pub struct UnlockTokens<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
+    pub user_info: Account<'info, UserInfo>,
+    #[account(
+        mut,
+        seeds = [
+            b"lock",
+            program_id.as_ref(),
+            user_wallet.key().as_ref(),
+            mint.key().as_ref(),
+            &lock_id.to_le_bytes()
+        ],
+        bump,
+        close = user_wallet
+    )]
+    pub lock_position: Account<'info, LockPosition>,
+    #[account(
+        mut,
+        seeds = [b"stake_vault", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        token::authority = vault_authority
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    /// Receives early-unlock penalties on their way to the DEX adapter
+    #[account(mut, seeds = [b"token_vault", program_id.as_ref(), mint.key().as_ref()], bump, token::authority = vault_authority)]
+    pub token_vault: Account<'info, TokenAccount>,
+    /// CHECK: PDA used as token vault authority
+    #[account(
+        seeds = [b"vault_authority", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub user_wallet: Signer<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut, token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncBalance<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
+    pub user_info: Account<'info, UserInfo>,
+    /// CHECK: holder being synced; receives owed lamports unless they set a
+    /// custom claim destination, in which case they are carried over
+    #[account(mut)]
+    pub user_wallet: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncBalances<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchClaim<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecycleStaleRewards<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
+    pub user_info: Account<'info, UserInfo>,
+    /// CHECK: dormant holder; only used to derive `user_info`
+    pub user_wallet: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    /// Receives the `UserInfo` rent when closing
+    #[account(mut)]
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardExclusion<'info> {
+    #[account(
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = UserInfo::LEN + 8,
+        seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub user_info: Account<'info, UserInfo>,
+    /// CHECK: wallet being excluded or re-included; only receives owed lamports
+    #[account(mut)]
+    pub user_wallet: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(epoch_id: u64, merkle_root: [u8; 32], total_lamports: u64, leaf_count: u32)]
+pub struct PublishEpoch<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = publisher,
+        space = Epoch::space(leaf_count) + 8,
+        seeds = [b"epoch", program_id.as_ref(), mint.key().as_ref(), &epoch_id.to_le_bytes()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub publisher: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct ClaimEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch", program_id.as_ref(), mint.key().as_ref(), &epoch_id.to_le_bytes()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetEpochPublisher<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateConfig<'info> {
     #[account(
@@ -121,6 +489,156 @@ pub struct UpdateConfig<'info> {
     pub owner: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct SetRewardDuration<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetStakingMode<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetLockTiers<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardExpiry<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMinRewardBalance<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTaxRounding<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetNftDiscount<'info> {
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    pub mint: Account<'info, Mint>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTaxSchedule<'info> {
+    #[account(
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = TaxSchedule::LEN + 8,
+        seeds = [b"tax_schedule", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub tax_schedule: Account<'info, TaxSchedule>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PruneTaxSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"tax_schedule", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub tax_schedule: Account<'info, TaxSchedule>,
+    pub mint: Account<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct DonateRewards<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub donor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuditSolvency<'info> {
+    #[account(mut, seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution; only its lamports are read
+    pub reward_vault: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+}
 
 #[derive(Accounts)]
 pub struct UpdateTotalSupply<'info> {
@@ -140,6 +658,113 @@ pub struct UpdateTotalSupply<'info> {
     pub owner: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct MintWithTracking<'info> {
+    #[account(
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(mut, mint::authority = mint_authority)]
+    pub mint: Account<'info, Mint>,
+    /// CHECK: PDA holding the mint authority
+    #[account(
+        seeds = [b"mint_authority", program_id.as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = UserInfo::LEN + 8,
+        seeds = [b"user", program_id.as_ref(), recipient.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub recipient_info: Account<'info, UserInfo>,
+    /// CHECK: receives the minted tokens and any owed rewards
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    #[account(mut, token::mint = mint, token::authority = recipient)]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BurnWithTracking<'info> {
+    #[account(seeds = [b"config", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut, seeds = [b"reward_vault", program_id.as_ref(), mint.key().as_ref()], bump)]
+    /// CHECK: SOL vault for distribution
+    pub reward_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut, seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()], bump)]
+    pub user_info: Account<'info, UserInfo>,
+    #[account(mut)]
+    pub user_wallet: Signer<'info>,
+    #[account(mut, token::mint = mint, token::authority = user_wallet)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    /// CHECK: may predate the current layout, so it is grown and decoded
+    /// (checking the discriminator) in the handler
+    #[account(
+        mut,
+        seeds = [b"config", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub config: AccountInfo<'info>,
+    /// CHECK: as `config`
+    #[account(
+        mut,
+        seeds = [b"global", program_id.as_ref(), mint.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub global_state: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserInfo<'info> {
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
+    /// CHECK: may predate the current layout, so it is grown and decoded
+    /// (checking the discriminator) in the handler
+    #[account(
+        mut,
+        seeds = [b"user", program_id.as_ref(), user_wallet.key().as_ref(), mint.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    pub user_info: AccountInfo<'info>,
+    /// CHECK: holder whose account is migrated; only used to derive `user_info`
+    pub user_wallet: AccountInfo<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct CloseUserInfo<'info> {
     #[account(
@@ -149,6 +774,8 @@ pub struct CloseUserInfo<'info> {
         close = authority
     )]
     pub user_info: Account<'info, UserInfo>,
+    #[account(mut, seeds = [b"global", program_id.as_ref(), mint.key().as_ref()], bump)]
+    pub global_state: Account<'info, GlobalState>,
     pub mint: Account<'info, Mint>,
     #[account(mut)]
     pub user_wallet: Signer<'info>,
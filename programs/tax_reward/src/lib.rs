@@ -1,10 +1,18 @@
 // solana_tax_reward program entrypoint using Anchor
-use crate::{error::TaxRewardError, instructions::*};
+use crate::{
+    error::TaxRewardError,
+    instructions::*,
+    state::{BatchClaimResult, BatchClaimStatus, LockTier, PendingRewards, TaxRounding, TaxWindow},
+};
 use anchor_lang::prelude::*;
 use anchor_spl::token;
 // Module declarations
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod math;
+pub mod merkle;
+pub mod nft;
 pub mod state;
 pub mod swap;
 
@@ -16,9 +24,6 @@ declare_id!("2A1sUwgtyMHswvfY2N4UTZLyPKLNjEHmhRzYFZgQgHQF");
 pub mod solana_tax_reward {
     use super::*;
 
-    // Scale factor for cumulative reward accounting (1e18)
-    const SCALE: u128 = 1_000_000_000_000_000_000;
-
     /// Initialize the program; called once by deployer
     pub fn initialize(
         ctx: Context<Initialize>,
@@ -44,10 +49,36 @@ pub mod solana_tax_reward {
         cfg.owner = *ctx.accounts.authority.key;
         cfg.dex_program = dex_program;
         cfg.paused = false;
+        cfg.nft_discount_collection = Pubkey::default();
+        cfg.nft_discount_bps = 0;
+        cfg.tax_rounding = TaxRounding::Floor;
+        cfg.min_reward_balance = 0;
+        cfg.reward_duration = 0;
+        cfg.epoch_publisher = Pubkey::default();
+        cfg.reward_expiry_secs = 0;
+        cfg.staking_mode = false;
+        cfg.lock_tiers = [LockTier::default(); state::MAX_LOCK_TIERS];
+        cfg.early_unlock_penalty_bps = 0;
 
         let global = &mut ctx.accounts.global_state;
         global.total_supply = ctx.accounts.mint.supply;
         global.cum_reward_per_token = 0;
+        global.tax_dust = 0;
+        global.reward_dust = 0;
+        global.eligible_supply = 0;
+        global.reward_rate = 0;
+        global.period_finish = 0;
+        global.last_update_time = Clock::get()?.unix_timestamp;
+        global.total_tokens_taxed = 0;
+        global.total_sol_swapped = 0;
+        global.total_sol_claimed = 0;
+        global.swap_count = 0;
+        global.claim_count = 0;
+        global.unique_holders = 0;
+        global.largest_swap = 0;
+        global.outstanding_liability = 0;
+
+        ctx.accounts.tax_schedule.windows = Vec::new();
 
         msg!(
             "Program initialized: tax_rate={}bps, total_supply={}",
@@ -93,45 +124,21 @@ pub mod solana_tax_reward {
         require!(global.total_supply > 0, TaxRewardError::InvalidMintSupply);
 
         // 1. Lazy pull pending rewards before user interaction
+        let now = Clock::get()?.unix_timestamp;
         let global = &mut ctx.accounts.global_state;
+        accrue_rewards(global, now)?;
         let user_info = &mut ctx.accounts.user_info;
-        let owed = calculate_owed_rewards(
-            user_info.balance_snapshot,
-            global.cum_reward_per_token,
-            user_info.last_cum,
+        pay_owed_rewards(
+            ctx.program_id,
+            &ctx.accounts.mint.key(),
+            global,
+            user_info,
+            ctx.accounts.user_wallet.key,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.user_wallet.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
         )?;
-
-        if owed > 0 {
-            msg!("Transferring owed rewards: {}", owed);
-            let rv = ctx.accounts.reward_vault.to_account_info();
-            let ix = anchor_lang::solana_program::system_instruction::transfer(
-                rv.key,
-                ctx.accounts.user_wallet.key,
-                owed,
-            );
-            let mint_key = ctx.accounts.mint.key();
-            let (_, reward_vault_bump) = Pubkey::find_program_address(
-                &[b"reward_vault", ctx.program_id.as_ref(), mint_key.as_ref()],
-                ctx.program_id,
-            );
-            let reward_vault_seeds = &[
-                b"reward_vault",
-                ctx.program_id.as_ref(),
-                mint_key.as_ref(),
-                &[reward_vault_bump],
-            ];
-            anchor_lang::solana_program::program::invoke_signed(
-                &ix,
-                &[
-                    rv.clone(),
-                    ctx.accounts.user_wallet.to_account_info().clone(),
-                    ctx.accounts.system_program.to_account_info().clone(),
-                ],
-                &[reward_vault_seeds],
-            )?;
-        }
-        // update user last_cum
-        user_info.last_cum = global.cum_reward_per_token;
+        touch_holder(global, user_info, now)?;
 
         // 2. Trigger token swap via DEX adapter (external CPI)
         // record SOL balance before swap
@@ -172,36 +179,52 @@ pub mod solana_tax_reward {
             return Err(TaxRewardError::SlippageExceeded.into());
         }
 
-        // Ensure reward vault has sufficient balance for pending rewards
-        let rv_balance = **rv_info.lamports.borrow();
-        if owed > 0 && rv_balance < owed {
-            msg!(
-                "Reward vault balance {} insufficient for owed rewards {}",
-                rv_balance,
-                owed
-            );
-            return Err(TaxRewardError::InsufficientRewardVault.into());
-        }
+        // 3. Update cumulative reward accounting (instantly or streamed)
+        deposit_rewards(cfg, global, swapped_amount, now)?;
 
-        // 3. Update cumulative reward accounting...
-        let delta_cum = delta_sol
-            .checked_mul(SCALE)
-            .ok_or(TaxRewardError::Overflow)?
-            .checked_div(global.total_supply as u128)
-            .ok_or(TaxRewardError::Overflow)?;
-        global.cum_reward_per_token = global
-            .cum_reward_per_token
-            .checked_add(delta_cum)
-            .ok_or(TaxRewardError::Overflow)?;
+        // 4. Collect tax, honouring any active tax holiday window. A window
+        // never raises the rate, even if the base rate was cut after it was set
+        let holiday_rate = ctx
+            .accounts
+            .tax_schedule
+            .as_ref()
+            .and_then(|schedule| schedule.active_rate(now))
+            .map(|override_bps| override_bps.min(cfg.tax_rate_bps));
+        let tax_rate_bps = match holiday_rate {
+            Some(override_bps) => {
+                msg!(
+                    "Tax holiday active: {}bps instead of {}bps",
+                    override_bps,
+                    cfg.tax_rate_bps
+                );
+                override_bps
+            }
+            None => cfg.tax_rate_bps,
+        };
+        let tax_rate_bps = match (&ctx.accounts.nft_token_account, &ctx.accounts.nft_metadata) {
+            (Some(nft_token_account), Some(nft_metadata)) => {
+                verify_nft_discount(
+                    cfg,
+                    ctx.accounts.user_wallet.key,
+                    nft_token_account,
+                    nft_metadata,
+                )?;
+                msg!("NFT discount applied: -{}bps", cfg.nft_discount_bps);
+                tax_rate_bps.saturating_sub(cfg.nft_discount_bps)
+            }
+            (None, None) => tax_rate_bps,
+            _ => return Err(TaxRewardError::InvalidNftDiscount.into()),
+        };
+        let (tax_amount, tax_dust) =
+            math::calculate_tax(amount_in, tax_rate_bps, cfg.tax_rounding, global.tax_dust)
+                .ok_or(TaxRewardError::Overflow)?;
+        global.tax_dust = tax_dust;
+        msg!(
+            "Transferring taxed tokens: {} (carried dust {})",
+            tax_amount,
+            tax_dust
+        );
 
-        // 4. Collect tax...
-        let tax_amount = amount_in
-            .checked_mul(cfg.tax_rate_bps as u64)
-            .ok_or(TaxRewardError::Overflow)?
-            .checked_div(10_000)
-            .ok_or(TaxRewardError::Overflow)?;
-        msg!("Transferring taxed tokens: {}", tax_amount);
-        
         // Create tax transfer context before borrowing user_info mutably again
         let tax_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -212,58 +235,746 @@ pub mod solana_tax_reward {
             },
         );
         token::transfer(tax_ctx, tax_amount)?;
+        user_info
+            .record_taxed_trade(tax_amount)
+            .ok_or(TaxRewardError::Overflow)?;
+        global
+            .record_swap(tax_amount, swapped_amount)
+            .ok_or(TaxRewardError::Overflow)?;
 
         // 5. Snapshot user's new balance
-        user_info.balance_snapshot = ctx.accounts.user_token_account.amount;
+        ctx.accounts.user_token_account.reload()?;
+        let weight = cfg.reward_weight(user_info, ctx.accounts.user_token_account.amount);
+        update_snapshot(global, user_info, weight)?;
 
         Ok(())
     }
 
     /// Allows any holder to settle pending SOL rewards
+    /// The holder or their claim delegate may call this; the payout always
+    /// goes to the holder's chosen claim destination.
     pub fn claim_rewards(ctx: Context<Claim>) -> Result<()> {
-        msg!("claim_rewards: user={}", ctx.accounts.user_wallet.key);
-        let global = &ctx.accounts.global_state;
+        msg!(
+            "claim_rewards: user={}, caller={}",
+            ctx.accounts.user_wallet.key,
+            ctx.accounts.caller.key
+        );
+        let caller = ctx.accounts.caller.key();
+        require!(
+            caller == ctx.accounts.user_wallet.key()
+                || ctx.accounts.user_info.claim_delegate == Some(caller),
+            TaxRewardError::Unauthorized
+        );
+        require!(
+            ctx.accounts.claim_destination.key()
+                == claim_destination(&ctx.accounts.user_info, ctx.accounts.user_wallet.key),
+            TaxRewardError::InvalidClaimDestination
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let global = &mut ctx.accounts.global_state;
+        accrue_rewards(global, now)?;
         let user_info = &mut ctx.accounts.user_info;
+        touch_holder(global, user_info, now)?;
 
-        // calculate owed rewards
-        let owed = calculate_owed_rewards(
-            user_info.balance_snapshot,
-            global.cum_reward_per_token,
-            user_info.last_cum,
+        let paid = pay_owed_rewards(
+            ctx.program_id,
+            &ctx.accounts.mint.key(),
+            global,
+            user_info,
+            ctx.accounts.user_wallet.key,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.claim_destination,
+            &ctx.accounts.system_program.to_account_info(),
         )?;
+        require!(
+            paid > 0 || user_info.unpaid_rewards == 0,
+            TaxRewardError::RewardVaultBelowRentFloor
+        );
+        // update snapshot points
+        user_info.last_claim_slot = Clock::get()?.slot;
+        let weight = ctx
+            .accounts
+            .config
+            .reward_weight(user_info, ctx.accounts.user_token_account.amount);
+        update_snapshot(global, user_info, weight)?;
 
-        if owed > 0 {
-            let rv = ctx.accounts.reward_vault.to_account_info();
-            let ix = anchor_lang::solana_program::system_instruction::transfer(
-                rv.key,
-                ctx.accounts.user_wallet.key,
-                owed,
-            );
-            let mint_key = ctx.accounts.mint.key();
-            let (_, reward_vault_bump) = Pubkey::find_program_address(
-                &[b"reward_vault", ctx.program_id.as_ref(), mint_key.as_ref()],
+        Ok(())
+    }
+
+    /// Read-only view for `simulateTransaction`: returns `PendingRewards`
+    /// through `set_return_data`, accruing any streamed emission on a copy of
+    /// the global state so nothing is written.
+    pub fn get_pending_rewards(ctx: Context<GetPendingRewards>) -> Result<()> {
+        msg!("get_pending_rewards: user={}", ctx.accounts.user_wallet.key);
+        let mut global = (*ctx.accounts.global_state).clone();
+        accrue_rewards(&mut global, Clock::get()?.unix_timestamp)?;
+
+        let user_info = &ctx.accounts.user_info;
+        let pending = PendingRewards {
+            owed: calculate_owed_rewards(
+                user_info.balance_snapshot,
+                global.cum_reward_per_token,
+                user_info.last_cum,
+            )?
+            .checked_add(user_info.unpaid_rewards)
+            .ok_or(TaxRewardError::Overflow)?,
+            balance_snapshot: user_info.balance_snapshot,
+            last_claim_slot: user_info.last_claim_slot,
+            token_balance: ctx.accounts.user_token_account.amount,
+        };
+        anchor_lang::solana_program::program::set_return_data(&pending.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Set where claimed rewards are paid and who may trigger claims.
+    /// `None` pays the wallet itself / allows only the wallet to claim.
+    pub fn set_claim_settings(
+        ctx: Context<SetClaimSettings>,
+        destination: Option<Pubkey>,
+        delegate: Option<Pubkey>,
+    ) -> Result<()> {
+        msg!(
+            "set_claim_settings: user={}, destination={:?}, delegate={:?}",
+            ctx.accounts.user_wallet.key,
+            destination,
+            delegate
+        );
+        let user_info = &mut ctx.accounts.user_info;
+        user_info.claim_destination = destination;
+        user_info.claim_delegate = delegate;
+        touch_holder(
+            &mut ctx.accounts.global_state,
+            user_info,
+            Clock::get()?.unix_timestamp,
+        )?;
+        Ok(())
+    }
+
+    /// Deposit tokens into the stake vault. In staking mode only staked
+    /// tokens earn rewards; pending rewards are settled first.
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        msg!(
+            "stake: user={}, amount={}",
+            ctx.accounts.user_wallet.key,
+            amount
+        );
+        require!(!ctx.accounts.config.paused, TaxRewardError::ProgramPaused);
+        require!(
+            ctx.accounts.config.staking_mode,
+            TaxRewardError::StakingDisabled
+        );
+        require!(amount > 0, TaxRewardError::InvalidInstruction);
+
+        let now = Clock::get()?.unix_timestamp;
+        accrue_rewards(&mut ctx.accounts.global_state, now)?;
+
+        let stake_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
+                authority: ctx.accounts.user_wallet.to_account_info(),
+            },
+        );
+        token::transfer(stake_ctx, amount)?;
+        ctx.accounts.user_token_account.reload()?;
+
+        let accounts = &mut *ctx.accounts;
+        let user_info = &mut accounts.user_info;
+        user_info.staked_amount = user_info
+            .staked_amount
+            .checked_add(amount)
+            .ok_or(TaxRewardError::Overflow)?;
+        touch_holder(&mut accounts.global_state, user_info, now)?;
+        sync_holder(
+            ctx.program_id,
+            &accounts.mint.key(),
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.user_info,
+            &accounts.user_token_account,
+            &accounts.reward_vault,
+            &accounts.user_wallet.to_account_info(),
+            &accounts.system_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+
+    /// Withdraw staked tokens. Always allowed, even when paused or after
+    /// staking mode is switched off.
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        msg!(
+            "unstake: user={}, amount={}",
+            ctx.accounts.user_wallet.key,
+            amount
+        );
+        require!(amount > 0, TaxRewardError::InvalidInstruction);
+        require!(
+            ctx.accounts.user_info.staked_amount >= amount,
+            TaxRewardError::InsufficientFunds
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        accrue_rewards(&mut ctx.accounts.global_state, now)?;
+
+        let mint_key = ctx.accounts.mint.key();
+        transfer_from_vault(
+            ctx.program_id,
+            &mint_key,
+            &ctx.accounts.token_program,
+            &ctx.accounts.stake_vault,
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.vault_authority,
+            amount,
+        )?;
+        ctx.accounts.user_token_account.reload()?;
+
+        let accounts = &mut *ctx.accounts;
+        let user_info = &mut accounts.user_info;
+        user_info.staked_amount -= amount;
+        touch_holder(&mut accounts.global_state, user_info, now)?;
+        sync_holder(
+            ctx.program_id,
+            &mint_key,
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.user_info,
+            &accounts.user_token_account,
+            &accounts.reward_vault,
+            &accounts.user_wallet.to_account_info(),
+            &accounts.system_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+
+    /// Lock tokens in the stake vault for one of `Config::lock_tiers`. The
+    /// position earns rewards on `amount` times the tier multiplier.
+    pub fn lock_tokens(
+        ctx: Context<LockTokens>,
+        lock_id: u64,
+        amount: u64,
+        tier: u8,
+    ) -> Result<()> {
+        msg!(
+            "lock_tokens: user={}, lock_id={}, amount={}, tier={}",
+            ctx.accounts.user_wallet.key,
+            lock_id,
+            amount,
+            tier
+        );
+        let cfg = &ctx.accounts.config;
+        require!(!cfg.paused, TaxRewardError::ProgramPaused);
+        require!(cfg.staking_mode, TaxRewardError::StakingDisabled);
+        require!(amount > 0, TaxRewardError::InvalidInstruction);
+        let lock_tier = cfg
+            .lock_tiers
+            .get(tier as usize)
+            .filter(|t| t.is_enabled())
+            .copied()
+            .ok_or(TaxRewardError::InvalidLockTier)?;
+        let weight =
+            math::apply_bps(amount, lock_tier.multiplier_bps).ok_or(TaxRewardError::Overflow)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        accrue_rewards(&mut ctx.accounts.global_state, now)?;
+
+        let lock_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
+                authority: ctx.accounts.user_wallet.to_account_info(),
+            },
+        );
+        token::transfer(lock_ctx, amount)?;
+        ctx.accounts.user_token_account.reload()?;
+
+        let position = &mut ctx.accounts.lock_position;
+        position.owner = ctx.accounts.user_wallet.key();
+        position.lock_id = lock_id;
+        position.amount = amount;
+        position.weight = weight;
+        position.start_ts = now;
+        position.unlock_ts = now
+            .checked_add(lock_tier.duration_secs)
+            .ok_or(TaxRewardError::Overflow)?;
+        msg!(
+            "Locked {} tokens until {} with weight {}",
+            amount,
+            position.unlock_ts,
+            weight
+        );
+
+        let accounts = &mut *ctx.accounts;
+        let user_info = &mut accounts.user_info;
+        user_info.locked_weight = user_info
+            .locked_weight
+            .checked_add(weight)
+            .ok_or(TaxRewardError::Overflow)?;
+        touch_holder(&mut accounts.global_state, user_info, now)?;
+        sync_holder(
+            ctx.program_id,
+            &accounts.mint.key(),
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.user_info,
+            &accounts.user_token_account,
+            &accounts.reward_vault,
+            &accounts.user_wallet.to_account_info(),
+            &accounts.system_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+
+    /// Close a lock position and return its tokens. Unlocking before
+    /// `unlock_ts` forfeits `Config::early_unlock_penalty_bps` of the amount,
+    /// which is swapped to SOL through the DEX adapter (at least
+    /// `min_penalty_out` lamports) and deposited as rewards for the holders
+    /// that remain, after the unlocking holder's own weight is removed.
+    pub fn unlock_tokens(
+        ctx: Context<UnlockTokens>,
+        lock_id: u64,
+        min_penalty_out: u64,
+    ) -> Result<()> {
+        msg!(
+            "unlock_tokens: user={}, lock_id={}, min_penalty_out={}",
+            ctx.accounts.user_wallet.key,
+            lock_id,
+            min_penalty_out
+        );
+        let now = Clock::get()?.unix_timestamp;
+        accrue_rewards(&mut ctx.accounts.global_state, now)?;
+
+        let position = &ctx.accounts.lock_position;
+        let penalty = if position.is_unlocked(now) {
+            0
+        } else {
+            math::apply_bps(
+                position.amount,
+                ctx.accounts.config.early_unlock_penalty_bps,
+            )
+            .ok_or(TaxRewardError::Overflow)?
+        };
+        let returned = position.amount - penalty;
+        let weight = position.weight;
+        msg!(
+            "Unlocking {} tokens: returned {}, penalty {}",
+            position.amount,
+            returned,
+            penalty
+        );
+
+        let mint_key = ctx.accounts.mint.key();
+        if penalty > 0 {
+            transfer_from_vault(
+                ctx.program_id,
+                &mint_key,
+                &ctx.accounts.token_program,
+                &ctx.accounts.stake_vault,
+                &ctx.accounts.token_vault.to_account_info(),
+                &ctx.accounts.vault_authority,
+                penalty,
+            )?;
+        }
+        if returned > 0 {
+            transfer_from_vault(
+                ctx.program_id,
+                &mint_key,
+                &ctx.accounts.token_program,
+                &ctx.accounts.stake_vault,
+                &ctx.accounts.user_token_account.to_account_info(),
+                &ctx.accounts.vault_authority,
+                returned,
+            )?;
+        }
+        ctx.accounts.user_token_account.reload()?;
+
+        let accounts = &mut *ctx.accounts;
+        let user_info = &mut accounts.user_info;
+        user_info.locked_weight = user_info
+            .locked_weight
+            .checked_sub(weight)
+            .ok_or(TaxRewardError::Overflow)?;
+        touch_holder(&mut accounts.global_state, user_info, now)?;
+        sync_holder(
+            ctx.program_id,
+            &mint_key,
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.user_info,
+            &accounts.user_token_account,
+            &accounts.reward_vault,
+            &accounts.user_wallet.to_account_info(),
+            &accounts.system_program.to_account_info(),
+        )?;
+
+        if penalty > 0 {
+            let pre_balance = accounts.reward_vault.lamports();
+            crate::swap::swap_tokens_for_sol(
                 ctx.program_id,
-            );
-            let reward_vault_seeds = &[
-                b"reward_vault",
-                ctx.program_id.as_ref(),
-                mint_key.as_ref(),
-                &[reward_vault_bump],
-            ];
-            anchor_lang::solana_program::program::invoke_signed(
-                &ix,
                 &[
-                    rv.clone(),
-                    ctx.accounts.user_wallet.to_account_info().clone(),
-                    ctx.accounts.system_program.to_account_info().clone(),
+                    accounts.token_vault.to_account_info(),
+                    accounts.reward_vault.clone(),
                 ],
-                &[reward_vault_seeds],
+                penalty,
+                min_penalty_out,
+            )?;
+            let swapped_amount = accounts
+                .reward_vault
+                .lamports()
+                .checked_sub(pre_balance)
+                .ok_or(TaxRewardError::Overflow)?;
+            msg!(
+                "Penalty swap: {} tokens -> {} lamports",
+                penalty,
+                swapped_amount
+            );
+            if swapped_amount < min_penalty_out {
+                return Err(TaxRewardError::SlippageExceeded.into());
+            }
+            deposit_rewards(
+                &accounts.config,
+                &mut accounts.global_state,
+                swapped_amount,
+                now,
             )?;
         }
-        // update snapshot points
+        Ok(())
+    }
+
+    /// Permissionless: settle a holder's rewards at their old snapshot and
+    /// re-snapshot from their live token account
+    pub fn sync_balance(ctx: Context<SyncBalance>) -> Result<()> {
+        msg!("sync_balance: user={}", ctx.accounts.user_wallet.key);
+        let accounts = &mut *ctx.accounts;
+        accrue_rewards(&mut accounts.global_state, Clock::get()?.unix_timestamp)?;
+        sync_holder(
+            ctx.program_id,
+            &accounts.mint.key(),
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.user_info,
+            &accounts.user_token_account,
+            &accounts.reward_vault,
+            &accounts.user_wallet,
+            &accounts.system_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+
+    /// Permissionless batch form of `sync_balance`. `remaining_accounts` holds
+    /// (user_info, user_wallet, user_token_account) triples.
+    pub fn sync_balances<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncBalances<'info>>,
+    ) -> Result<()> {
+        msg!(
+            "sync_balances: {} remaining accounts",
+            ctx.remaining_accounts.len()
+        );
+        require!(
+            ctx.remaining_accounts.len() % 3 == 0,
+            TaxRewardError::InvalidInstruction
+        );
+
+        accrue_rewards(&mut ctx.accounts.global_state, Clock::get()?.unix_timestamp)?;
+        let mint_key = ctx.accounts.mint.key();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        for triple in ctx.remaining_accounts.chunks(3) {
+            let (mut user_info, user_token_account) = load_holder(
+                ctx.program_id,
+                &mint_key,
+                &triple[0],
+                &triple[1],
+                &triple[2],
+            )?;
+            sync_holder(
+                ctx.program_id,
+                &mint_key,
+                &ctx.accounts.config,
+                &mut ctx.accounts.global_state,
+                &mut user_info,
+                &user_token_account,
+                &ctx.accounts.reward_vault,
+                &triple[1],
+                &system_program,
+            )?;
+            user_info.exit(ctx.program_id)?;
+        }
+        Ok(())
+    }
+
+    /// Permissionless claim crank over (user_info, user_wallet,
+    /// user_token_account) triples in `remaining_accounts`. Entries failing
+    /// the account or destination checks are skipped; a settlement error
+    /// aborts the batch. A `Vec<BatchClaimResult>` in entry order is returned
+    /// through `set_return_data`.
+    pub fn batch_claim<'info>(ctx: Context<'_, '_, '_, 'info, BatchClaim<'info>>) -> Result<()> {
+        msg!(
+            "batch_claim: {} remaining accounts",
+            ctx.remaining_accounts.len()
+        );
+        require!(
+            ctx.remaining_accounts.len() % 3 == 0,
+            TaxRewardError::InvalidInstruction
+        );
+
+        let clock = Clock::get()?;
+        let slot = clock.slot;
+        accrue_rewards(&mut ctx.accounts.global_state, clock.unix_timestamp)?;
+        let mint_key = ctx.accounts.mint.key();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let mut results = Vec::with_capacity(ctx.remaining_accounts.len() / 3);
+        for triple in ctx.remaining_accounts.chunks(3) {
+            let user_wallet = &triple[1];
+            let (mut user_info, user_token_account) = match load_holder(
+                ctx.program_id,
+                &mint_key,
+                &triple[0],
+                user_wallet,
+                &triple[2],
+            ) {
+                Ok(holder) => holder,
+                Err(_) => {
+                    msg!("Skipping {}: invalid accounts", user_wallet.key);
+                    results.push(BatchClaimResult::skipped(BatchClaimStatus::InvalidAccounts));
+                    continue;
+                }
+            };
+
+            // Only the wallet itself is passed, so custom destinations must claim directly
+            if claim_destination(&user_info, user_wallet.key) != *user_wallet.key {
+                msg!("Skipping {}: custom claim destination", user_wallet.key);
+                results.push(BatchClaimResult::skipped(
+                    BatchClaimStatus::DestinationMismatch,
+                ));
+                continue;
+            }
+
+            // Settlement moves lamports, so its errors abort the whole batch
+            // rather than leaving a paid entry unsaved
+            let amount = sync_holder(
+                ctx.program_id,
+                &mint_key,
+                &ctx.accounts.config,
+                &mut ctx.accounts.global_state,
+                &mut user_info,
+                &user_token_account,
+                &ctx.accounts.reward_vault,
+                user_wallet,
+                &system_program,
+            )?;
+            let status = if amount > 0 {
+                user_info.last_claim_slot = slot;
+                BatchClaimStatus::Paid
+            } else {
+                BatchClaimStatus::NothingOwed
+            };
+            user_info.exit(ctx.program_id)?;
+            results.push(BatchClaimResult { status, amount });
+        }
+
+        anchor_lang::solana_program::program::set_return_data(&results.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Permissionless: forfeit the pending rewards of a holder inactive for
+    /// `Config::reward_expiry_secs` and redistribute them to everyone else.
+    /// With `close`, the `UserInfo` is closed and its rent paid to the caller.
+    pub fn recycle_stale_rewards(ctx: Context<RecycleStaleRewards>, close: bool) -> Result<()> {
+        msg!(
+            "recycle_stale_rewards: user={}, caller={}, close={}",
+            ctx.accounts.user_wallet.key,
+            ctx.accounts.caller.key,
+            close
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let cfg = &ctx.accounts.config;
+        let user_info = &mut ctx.accounts.user_info;
+        require!(!user_info.excluded, TaxRewardError::RewardExcluded);
+        require!(
+            user_info.is_stale(now, cfg.reward_expiry_secs),
+            TaxRewardError::RewardsNotExpired
+        );
+
+        let global = &mut ctx.accounts.global_state;
+        accrue_rewards(global, now)?;
+        let forfeited = calculate_owed_rewards(
+            user_info.balance_snapshot,
+            global.cum_reward_per_token,
+            user_info.last_cum,
+        )?
+        .checked_add(user_info.unpaid_rewards)
+        .ok_or(TaxRewardError::Overflow)?;
+        user_info.unpaid_rewards = 0;
+
+        // Drop the holder's weight first so none of the forfeit flows back to them
         user_info.last_cum = global.cum_reward_per_token;
-        user_info.balance_snapshot = ctx.accounts.user_token_account.amount;
+        update_snapshot(global, user_info, 0)?;
+        if forfeited > 0 {
+            // Already a liability; redepositing must not count it twice
+            global.outstanding_liability = global.outstanding_liability.saturating_sub(forfeited);
+            deposit_rewards(cfg, global, forfeited, now)?;
+        }
+        msg!(
+            "Recycled {} lamports from {}",
+            forfeited,
+            ctx.accounts.user_wallet.key
+        );
+
+        if close {
+            require!(!user_info.holds_stake(), TaxRewardError::StakeNotEmpty);
+            user_info.close(ctx.accounts.caller.to_account_info())?;
+        } else {
+            let weight = cfg.reward_weight(user_info, ctx.accounts.user_token_account.amount);
+            update_snapshot(global, user_info, weight)?;
+        }
+        Ok(())
+    }
+
+    /// Governance admin: add or remove a wallet from the reward exclusion list.
+    /// Pending rewards are settled at the old snapshot before the weight changes.
+    pub fn set_reward_exclusion(ctx: Context<SetRewardExclusion>, excluded: bool) -> Result<()> {
+        msg!(
+            "set_reward_exclusion: owner={}, user={}, excluded={}",
+            ctx.accounts.owner.key,
+            ctx.accounts.user_wallet.key,
+            excluded
+        );
+        require!(
+            ctx.accounts.owner.key == &ctx.accounts.config.owner,
+            TaxRewardError::Unauthorized
+        );
 
+        let accounts = &mut *ctx.accounts;
+        let now = Clock::get()?.unix_timestamp;
+        accrue_rewards(&mut accounts.global_state, now)?;
+        // A freshly created account starts its expiry clock now, so a
+        // re-included wallet is not immediately stale and recyclable
+        if accounts.user_info.last_interaction_ts == 0 {
+            touch_holder(&mut accounts.global_state, &mut accounts.user_info, now)?;
+        }
+        accounts.user_info.excluded = excluded;
+        sync_holder(
+            ctx.program_id,
+            &accounts.mint.key(),
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.user_info,
+            &accounts.user_token_account,
+            &accounts.reward_vault,
+            &accounts.user_wallet,
+            &accounts.system_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+
+    /// Publish a per-epoch Merkle root of (index, wallet, lamports) leaves and
+    /// fund the epoch account with the lamports to be claimed
+    pub fn publish_epoch(
+        ctx: Context<PublishEpoch>,
+        epoch_id: u64,
+        merkle_root: [u8; 32],
+        total_lamports: u64,
+        leaf_count: u32,
+    ) -> Result<()> {
+        msg!(
+            "publish_epoch: publisher={}, epoch_id={}, total_lamports={}, leaf_count={}",
+            ctx.accounts.publisher.key,
+            epoch_id,
+            total_lamports,
+            leaf_count
+        );
+        let cfg = &ctx.accounts.config;
+        require!(
+            ctx.accounts.publisher.key == &cfg.owner
+                || ctx.accounts.publisher.key == &cfg.epoch_publisher,
+            TaxRewardError::Unauthorized
+        );
+        require!(
+            leaf_count > 0 && leaf_count <= state::Epoch::MAX_LEAVES,
+            TaxRewardError::InvalidEpoch
+        );
+
+        let epoch = &mut ctx.accounts.epoch;
+        epoch.epoch_id = epoch_id;
+        epoch.merkle_root = merkle_root;
+        epoch.total_lamports = total_lamports;
+        epoch.claimed_lamports = 0;
+        epoch.leaf_count = leaf_count;
+        epoch.claimed_bitmap = vec![0; state::Epoch::bitmap_len(leaf_count)];
+
+        let fund_ix = anchor_lang::solana_program::system_instruction::transfer(
+            ctx.accounts.publisher.key,
+            &epoch.key(),
+            total_lamports,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &fund_ix,
+            &[
+                ctx.accounts.publisher.to_account_info(),
+                epoch.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Claim an epoch allocation with a Merkle proof of (index, wallet, lamports)
+    pub fn claim_epoch(
+        ctx: Context<ClaimEpoch>,
+        epoch_id: u64,
+        index: u32,
+        lamports: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        msg!(
+            "claim_epoch: claimant={}, epoch_id={}, index={}, lamports={}",
+            ctx.accounts.claimant.key,
+            epoch_id,
+            index,
+            lamports
+        );
+        let epoch = &mut ctx.accounts.epoch;
+        require!(index < epoch.leaf_count, TaxRewardError::InvalidMerkleProof);
+        require!(!epoch.is_claimed(index), TaxRewardError::AlreadyClaimed);
+
+        let leaf = merkle::leaf_hash(index, ctx.accounts.claimant.key, lamports);
+        require!(
+            merkle::verify(&proof, &epoch.merkle_root, leaf),
+            TaxRewardError::InvalidMerkleProof
+        );
+
+        epoch.set_claimed(index);
+        epoch.claimed_lamports = epoch
+            .claimed_lamports
+            .checked_add(lamports)
+            .ok_or(TaxRewardError::Overflow)?;
+        require!(
+            epoch.claimed_lamports <= epoch.total_lamports,
+            TaxRewardError::InsufficientRewardVault
+        );
+
+        // The epoch account is program-owned, so lamports move directly
+        let epoch_info = epoch.to_account_info();
+        **epoch_info.try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.claimant.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    }
+
+    /// Governance admin: authorize a key to publish epoch roots
+    pub fn set_epoch_publisher(ctx: Context<SetEpochPublisher>, publisher: Pubkey) -> Result<()> {
+        msg!(
+            "set_epoch_publisher: owner={}, publisher={}",
+            ctx.accounts.owner.key,
+            publisher
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        cfg.epoch_publisher = publisher;
         Ok(())
     }
 
@@ -289,6 +1000,94 @@ pub mod solana_tax_reward {
         Ok(())
     }
 
+    /// Permissionless: grow the `Config` and `GlobalState` written by the
+    /// pre-upgrade program to their current layouts. New fields are
+    /// zero-filled and decode as their defaults (no tax holidays, instant
+    /// distribution, staking off); the payer funds the extra rent. Must run
+    /// before any other instruction after the upgrade.
+    pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+        msg!(
+            "migrate_state: mint={}, payer={}",
+            ctx.accounts.mint.key(),
+            ctx.accounts.payer.key
+        );
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        grow_account(
+            &ctx.accounts.config,
+            state::Config::LEN + 8,
+            &payer,
+            &system_program,
+        )?;
+        let legacy = grow_account(
+            &ctx.accounts.global_state,
+            state::GlobalState::LEN + 8,
+            &payer,
+            &system_program,
+        )?;
+
+        // Decoding checks the discriminators of both grown accounts
+        Account::<state::Config>::try_from(&ctx.accounts.config)?;
+        let mut global = Account::<state::GlobalState>::try_from(&ctx.accounts.global_state)?;
+        if global.last_update_time == 0 {
+            global.last_update_time = Clock::get()?.unix_timestamp;
+        }
+        if legacy {
+            // Everything distributed from here on excludes legacy snapshots
+            global.legacy_cum = global.cum_reward_per_token;
+        }
+        global.exit(ctx.program_id)?;
+        Ok(())
+    }
+
+    /// Permissionless: grow a `UserInfo` created under an older, shorter
+    /// layout to the current `UserInfo::LEN`. New fields are zero-filled and
+    /// decode as their defaults; the payer funds the extra rent. Timestamps
+    /// are backfilled so migrated holders do not look dormant.
+    ///
+    /// Short accounts were written by the pre-upgrade program, whose
+    /// snapshots were never added to `eligible_supply`. Their rewards are
+    /// settled at the old snapshot up to `GlobalState::legacy_cum` into
+    /// `unpaid_rewards` and the snapshot is zeroed; the next sync registers
+    /// the live balance. `migrate_state`
+    /// must have run first.
+    pub fn migrate_user_info(ctx: Context<MigrateUserInfo>) -> Result<()> {
+        msg!(
+            "migrate_user_info: user={}, payer={}",
+            ctx.accounts.user_wallet.key,
+            ctx.accounts.payer.key
+        );
+        let info = &ctx.accounts.user_info;
+        let legacy = grow_account(
+            info,
+            state::UserInfo::LEN + 8,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let mut user_info = Account::<state::UserInfo>::try_from(info)?;
+        let now = Clock::get()?.unix_timestamp;
+        let global = &mut ctx.accounts.global_state;
+        if legacy {
+            user_info
+                .retire_legacy_snapshot(global.legacy_cum, global.cum_reward_per_token)
+                .ok_or(TaxRewardError::Overflow)?;
+            msg!(
+                "Carried {} lamports from the legacy snapshot",
+                user_info.unpaid_rewards
+            );
+        }
+        if user_info.last_interaction_ts == 0 {
+            user_info.last_interaction_ts = now;
+        }
+        if user_info.first_interaction_ts == 0 {
+            user_info.first_interaction_ts = user_info.last_interaction_ts;
+            global.register_holder().ok_or(TaxRewardError::Overflow)?;
+        }
+        user_info.exit(ctx.program_id)?;
+        Ok(())
+    }
+
     /// Close and cleanup stale UserInfo account, reclaim rent
     pub fn close_user_info(ctx: Context<CloseUserInfo>) -> Result<()> {
         msg!(
@@ -296,13 +1095,110 @@ pub mod solana_tax_reward {
             ctx.accounts.user_info.to_account_info().key,
             ctx.accounts.authority.key
         );
+        // Excluded wallets must stay registered so they cannot re-enter rewards
+        require!(
+            !ctx.accounts.user_info.excluded,
+            TaxRewardError::RewardExcluded
+        );
+        require!(
+            !ctx.accounts.user_info.holds_stake(),
+            TaxRewardError::StakeNotEmpty
+        );
+
+        // Closed accounts no longer count towards the reward denominator
+        let global = &mut ctx.accounts.global_state;
+        accrue_rewards(global, Clock::get()?.unix_timestamp)?;
         let user_info = &mut ctx.accounts.user_info;
+        update_snapshot(global, user_info, 0)?;
         user_info.close(ctx.accounts.authority.to_account_info())?;
         Ok(())
     }
 
+    /// Add SOL to the holder reward pool from any signer. It is distributed
+    /// (or streamed) exactly like swap proceeds, so a donation smaller than
+    /// what is left of the current stream joins it without pushing back
+    /// `period_finish`. `RewardsDonated` records the donor and optional memo.
+    pub fn donate_rewards(
+        ctx: Context<DonateRewards>,
+        lamports: u64,
+        memo: Option<String>,
+    ) -> Result<()> {
+        msg!(
+            "donate_rewards: donor={}, lamports={}",
+            ctx.accounts.donor.key,
+            lamports
+        );
+        require!(lamports > 0, TaxRewardError::InvalidInstruction);
+        require!(
+            memo.as_ref()
+                .map_or(true, |m| m.len() <= events::MAX_DONATION_MEMO_LEN),
+            TaxRewardError::MemoTooLong
+        );
+
+        let donate_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.donor.to_account_info(),
+                to: ctx.accounts.reward_vault.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(donate_ctx, lamports)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let global = &mut ctx.accounts.global_state;
+        accrue_rewards(global, now)?;
+        deposit_rewards(&ctx.accounts.config, global, lamports, now)?;
+
+        emit!(events::RewardsDonated {
+            mint: ctx.accounts.mint.key(),
+            donor: ctx.accounts.donor.key(),
+            lamports,
+            memo,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Permissionless: compare the reward vault's spendable lamports with the
+    /// outstanding reward liability, emit `SolvencyAudited`, and pause the
+    /// program if the vault cannot cover it
+    pub fn audit_solvency(ctx: Context<AuditSolvency>) -> Result<()> {
+        msg!(
+            "audit_solvency: reward_vault={}",
+            ctx.accounts.reward_vault.key
+        );
+        let available_lamports = available_vault_lamports(&ctx.accounts.reward_vault)?;
+        let outstanding_liability = ctx.accounts.global_state.outstanding_liability;
+        let shortfall = outstanding_liability.saturating_sub(available_lamports);
+
+        let cfg = &mut ctx.accounts.config;
+        let paused = shortfall > 0 && !cfg.paused;
+        if paused {
+            msg!(
+                "Shortfall of {} lamports: liability {}, available {}; pausing",
+                shortfall,
+                outstanding_liability,
+                available_lamports
+            );
+            cfg.paused = true;
+        }
+
+        emit!(events::SolvencyAudited {
+            mint: ctx.accounts.mint.key(),
+            available_lamports,
+            outstanding_liability,
+            shortfall,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
     /// Update total supply tracking for accurate reward distribution
-    /// Called by admin when mint supply changes significantly
+    /// Called by admin when mint supply changes significantly.
+    /// `total_supply` is informational: rewards are spread over
+    /// `eligible_supply` with per-token increments fixed at deposit time, so
+    /// claims spanning a supply change are already exact without checkpoints.
     pub fn update_total_supply(ctx: Context<UpdateTotalSupply>) -> Result<()> {
         msg!("update_total_supply: owner={}", ctx.accounts.owner.key);
         let cfg = &ctx.accounts.config;
@@ -324,6 +1220,659 @@ pub mod solana_tax_reward {
 
         Ok(())
     }
+
+    /// Governance admin: mint tokens through the program-held mint authority
+    /// (`["mint_authority", program, mint]` PDA), updating `total_supply` and
+    /// the recipient's snapshot in the same instruction. The mint authority
+    /// must first be handed to the PDA with SPL `set_authority`.
+    pub fn mint_with_tracking(ctx: Context<MintWithTracking>, amount: u64) -> Result<()> {
+        msg!(
+            "mint_with_tracking: owner={}, recipient={}, amount={}",
+            ctx.accounts.owner.key,
+            ctx.accounts.recipient.key,
+            amount
+        );
+        require!(
+            ctx.accounts.owner.key == &ctx.accounts.config.owner,
+            TaxRewardError::Unauthorized
+        );
+        require!(amount > 0, TaxRewardError::InvalidInstruction);
+
+        let clock = Clock::get()?;
+        accrue_rewards(&mut ctx.accounts.global_state, clock.unix_timestamp)?;
+
+        let mint_key = ctx.accounts.mint.key();
+        let (_, mint_authority_bump) = Pubkey::find_program_address(
+            &[
+                b"mint_authority",
+                ctx.program_id.as_ref(),
+                mint_key.as_ref(),
+            ],
+            ctx.program_id,
+        );
+        let mint_authority_seeds = &[
+            b"mint_authority",
+            ctx.program_id.as_ref(),
+            mint_key.as_ref(),
+            &[mint_authority_bump],
+        ];
+        let signer_seeds = &[&mint_authority_seeds[..]];
+        let mint_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.recipient_token_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token::mint_to(mint_ctx, amount)?;
+        ctx.accounts.recipient_token_account.reload()?;
+        ctx.accounts.mint.reload()?;
+
+        let accounts = &mut *ctx.accounts;
+        let global = &mut accounts.global_state;
+        // Track the mint itself rather than a running delta, so supply
+        // minted or burned outside this program cannot drift the count
+        global.total_supply = accounts.mint.supply;
+        // A freshly created recipient starts its expiry clock now rather
+        // than looking dormant since 1970
+        if accounts.recipient_info.last_interaction_ts == 0 {
+            touch_holder(global, &mut accounts.recipient_info, clock.unix_timestamp)?;
+        }
+        sync_holder(
+            ctx.program_id,
+            &mint_key,
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.recipient_info,
+            &accounts.recipient_token_account,
+            &accounts.reward_vault,
+            &accounts.recipient,
+            &accounts.system_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+
+    /// Burn the caller's tokens, updating `total_supply` and their snapshot
+    /// in the same instruction
+    pub fn burn_with_tracking(ctx: Context<BurnWithTracking>, amount: u64) -> Result<()> {
+        msg!(
+            "burn_with_tracking: user={}, amount={}",
+            ctx.accounts.user_wallet.key,
+            amount
+        );
+        require!(amount > 0, TaxRewardError::InvalidInstruction);
+
+        let clock = Clock::get()?;
+        accrue_rewards(&mut ctx.accounts.global_state, clock.unix_timestamp)?;
+
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.mint.to_account_info(),
+                from: ctx.accounts.user_token_account.to_account_info(),
+                authority: ctx.accounts.user_wallet.to_account_info(),
+            },
+        );
+        token::burn(burn_ctx, amount)?;
+        ctx.accounts.user_token_account.reload()?;
+        ctx.accounts.mint.reload()?;
+
+        let accounts = &mut *ctx.accounts;
+        let global = &mut accounts.global_state;
+        global.total_supply = accounts.mint.supply;
+        touch_holder(global, &mut accounts.user_info, clock.unix_timestamp)?;
+        sync_holder(
+            ctx.program_id,
+            &accounts.mint.key(),
+            &accounts.config,
+            &mut accounts.global_state,
+            &mut accounts.user_info,
+            &accounts.user_token_account,
+            &accounts.reward_vault,
+            &accounts.user_wallet.to_account_info(),
+            &accounts.system_program.to_account_info(),
+        )?;
+        Ok(())
+    }
+
+    /// Governance admin: stream new rewards over `reward_duration` seconds
+    /// instead of distributing them instantly (0 restores instant mode)
+    pub fn set_reward_duration(
+        ctx: Context<SetRewardDuration>,
+        reward_duration: i64,
+    ) -> Result<()> {
+        msg!(
+            "set_reward_duration: owner={}, reward_duration={}",
+            ctx.accounts.owner.key,
+            reward_duration
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        require!(reward_duration >= 0, TaxRewardError::InvalidInstruction);
+        cfg.reward_duration = reward_duration;
+        Ok(())
+    }
+
+    /// Governance admin: switch between balance snapshots and staked-only
+    /// rewards. Existing snapshots are re-weighted as holders interact or
+    /// get synced, so crank `sync_balances` after switching.
+    pub fn set_staking_mode(ctx: Context<SetStakingMode>, enabled: bool) -> Result<()> {
+        msg!(
+            "set_staking_mode: owner={}, enabled={}",
+            ctx.accounts.owner.key,
+            enabled
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        cfg.staking_mode = enabled;
+        Ok(())
+    }
+
+    /// Governance admin: replace the lock tiers and the early-unlock penalty.
+    /// Open positions keep the multiplier and unlock time they were created with.
+    pub fn set_lock_tiers(
+        ctx: Context<SetLockTiers>,
+        tiers: Vec<LockTier>,
+        early_unlock_penalty_bps: u16,
+    ) -> Result<()> {
+        msg!(
+            "set_lock_tiers: owner={}, tiers={}, early_unlock_penalty_bps={}",
+            ctx.accounts.owner.key,
+            tiers.len(),
+            early_unlock_penalty_bps
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        require!(
+            tiers.len() <= state::MAX_LOCK_TIERS,
+            TaxRewardError::InvalidLockTier
+        );
+        require!(
+            early_unlock_penalty_bps <= 10_000,
+            TaxRewardError::InvalidLockTier
+        );
+
+        let mut lock_tiers = [LockTier::default(); state::MAX_LOCK_TIERS];
+        for (slot, tier) in lock_tiers.iter_mut().zip(tiers) {
+            require!(
+                tier.duration_secs > 0 && tier.multiplier_bps as u64 >= math::BPS_DENOMINATOR,
+                TaxRewardError::InvalidLockTier
+            );
+            *slot = tier;
+        }
+        cfg.lock_tiers = lock_tiers;
+        cfg.early_unlock_penalty_bps = early_unlock_penalty_bps;
+        Ok(())
+    }
+
+    /// Governance admin: let pending rewards of holders inactive for
+    /// `reward_expiry_secs` be recycled (0 disables expiry)
+    pub fn set_reward_expiry(ctx: Context<SetRewardExpiry>, reward_expiry_secs: i64) -> Result<()> {
+        msg!(
+            "set_reward_expiry: owner={}, reward_expiry_secs={}",
+            ctx.accounts.owner.key,
+            reward_expiry_secs
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        require!(reward_expiry_secs >= 0, TaxRewardError::InvalidInstruction);
+        cfg.reward_expiry_secs = reward_expiry_secs;
+        Ok(())
+    }
+
+    /// Governance admin: set the minimum balance that earns rewards.
+    /// Existing snapshots are re-weighted as holders interact or get synced.
+    pub fn set_min_reward_balance(
+        ctx: Context<SetMinRewardBalance>,
+        min_reward_balance: u64,
+    ) -> Result<()> {
+        msg!(
+            "set_min_reward_balance: owner={}, min_reward_balance={}",
+            ctx.accounts.owner.key,
+            min_reward_balance
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        cfg.min_reward_balance = min_reward_balance;
+        Ok(())
+    }
+
+    /// Governance admin: choose how fractional tax amounts are rounded
+    pub fn set_tax_rounding(ctx: Context<SetTaxRounding>, rounding: TaxRounding) -> Result<()> {
+        msg!(
+            "set_tax_rounding: owner={}, rounding={:?}",
+            ctx.accounts.owner.key,
+            rounding
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        cfg.tax_rounding = rounding;
+        Ok(())
+    }
+
+    /// Governance admin: configure the partner NFT collection discount
+    /// Setting `collection` to the default pubkey disables the discount
+    pub fn set_nft_discount(
+        ctx: Context<SetNftDiscount>,
+        collection: Pubkey,
+        discount_bps: u16,
+    ) -> Result<()> {
+        msg!(
+            "set_nft_discount: owner={}, collection={}, discount_bps={}",
+            ctx.accounts.owner.key,
+            collection,
+            discount_bps
+        );
+        let cfg = &mut ctx.accounts.config;
+        require!(
+            ctx.accounts.owner.key == &cfg.owner,
+            TaxRewardError::Unauthorized
+        );
+        require!(discount_bps <= 10_000, TaxRewardError::InvalidTaxRate);
+        cfg.nft_discount_collection = collection;
+        cfg.nft_discount_bps = discount_bps;
+        Ok(())
+    }
+
+    /// Governance admin: replace the scheduled tax holiday windows. A window
+    /// may only lower the rate, so skipping the schedule never pays less tax.
+    pub fn set_tax_schedule(ctx: Context<SetTaxSchedule>, windows: Vec<TaxWindow>) -> Result<()> {
+        msg!(
+            "set_tax_schedule: owner={}, windows={}",
+            ctx.accounts.owner.key,
+            windows.len()
+        );
+        require!(
+            ctx.accounts.owner.key == &ctx.accounts.config.owner,
+            TaxRewardError::Unauthorized
+        );
+        require!(
+            windows.len() <= crate::state::TaxSchedule::MAX_WINDOWS,
+            TaxRewardError::TooManyTaxWindows
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        for window in windows.iter() {
+            require!(
+                window.start_ts < window.end_ts && window.end_ts > now,
+                TaxRewardError::InvalidTaxWindow
+            );
+            require!(
+                window.tax_rate_bps <= ctx.accounts.config.tax_rate_bps,
+                TaxRewardError::InvalidTaxRate
+            );
+        }
+
+        ctx.accounts.tax_schedule.windows = windows;
+        Ok(())
+    }
+
+    /// Permissionless cleanup of tax holiday windows that have already ended
+    pub fn prune_tax_schedule(ctx: Context<PruneTaxSchedule>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let removed = ctx.accounts.tax_schedule.prune_expired(now);
+        msg!("prune_tax_schedule: removed {} expired windows", removed);
+        Ok(())
+    }
+}
+
+/// Verify the user holds an NFT from the configured verified collection
+fn verify_nft_discount(
+    cfg: &state::Config,
+    user_wallet: &Pubkey,
+    nft_token_account: &anchor_spl::token::TokenAccount,
+    nft_metadata: &AccountInfo,
+) -> Result<()> {
+    require!(
+        cfg.nft_discount_collection != Pubkey::default(),
+        TaxRewardError::InvalidNftDiscount
+    );
+    require!(
+        nft_token_account.owner == *user_wallet && nft_token_account.amount >= 1,
+        TaxRewardError::InvalidNftDiscount
+    );
+    require!(
+        *nft_metadata.owner == nft::TOKEN_METADATA_PROGRAM_ID
+            && *nft_metadata.key == nft::metadata_address(&nft_token_account.mint),
+        TaxRewardError::InvalidNftDiscount
+    );
+
+    let data = nft_metadata.try_borrow_data()?;
+    let metadata =
+        nft::parse_collection_metadata(&data).ok_or(TaxRewardError::InvalidNftDiscount)?;
+    require!(
+        metadata.mint == nft_token_account.mint
+            && nft::is_verified_member(&metadata, &cfg.nft_discount_collection),
+        TaxRewardError::InvalidNftDiscount
+    );
+    Ok(())
+}
+
+/// Account that receives a user's claimed rewards
+fn claim_destination(user_info: &state::UserInfo, user_wallet: &Pubkey) -> Pubkey {
+    user_info.claim_destination.unwrap_or(*user_wallet)
+}
+
+/// Accrue the streamed emission since `last_update_time` into
+/// `cum_reward_per_token`. Must run before any snapshot or supply change.
+fn accrue_rewards(global: &mut state::GlobalState, now: i64) -> Result<()> {
+    global.accrue(now).ok_or(TaxRewardError::Overflow)?;
+    Ok(())
+}
+
+/// Add new reward lamports, instantly or streamed over
+/// `Config::reward_duration`; see `GlobalState::deposit` for how deposits
+/// join a running stream. Callers must have accrued up to `now` first.
+fn deposit_rewards(
+    cfg: &state::Config,
+    global: &mut state::GlobalState,
+    lamports: u64,
+    now: i64,
+) -> Result<()> {
+    global
+        .deposit(lamports, cfg.reward_duration, now)
+        .ok_or(TaxRewardError::Overflow)?;
+    if lamports > 0 && cfg.reward_duration > 0 {
+        msg!(
+            "Streaming {} lamports until {}",
+            lamports,
+            global.period_finish
+        );
+    }
+    Ok(())
+}
+
+/// Settle a holder's owed rewards plus any earlier carry-over, paying as
+/// much as the reward vault holds above its rent floor. The remainder is
+/// kept in `UserInfo::unpaid_rewards`, as is everything when `destination`
+/// is not the holder's claim destination. Returns the lamports paid.
+#[allow(clippy::too_many_arguments)]
+fn pay_owed_rewards<'info>(
+    program_id: &Pubkey,
+    mint_key: &Pubkey,
+    global: &mut state::GlobalState,
+    user_info: &mut state::UserInfo,
+    user_wallet: &Pubkey,
+    reward_vault: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u64> {
+    let available = if *destination.key == claim_destination(user_info, user_wallet) {
+        available_vault_lamports(reward_vault)?
+    } else {
+        0
+    };
+    let (paid, due) = user_info
+        .settle(global.cum_reward_per_token, available)
+        .ok_or(TaxRewardError::Overflow)?;
+    if due == 0 {
+        return Ok(0);
+    }
+    if paid > 0 {
+        pay_from_reward_vault(
+            program_id,
+            mint_key,
+            reward_vault,
+            destination,
+            system_program,
+            paid,
+        )?;
+    }
+    record_claim(global, user_info, paid)?;
+    msg!(
+        "Paid {} of {} owed lamports, {} carried over",
+        paid,
+        due,
+        user_info.unpaid_rewards
+    );
+    Ok(paid)
+}
+
+/// Grow a program-owned account to `new_len`, zero-filling the new tail and
+/// topping up rent from `payer`. Returns whether the account was grown.
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    if account.data_len() >= new_len {
+        return Ok(false);
+    }
+    let top_up = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if top_up > 0 {
+        let top_up_ctx = CpiContext::new(
+            system_program.clone(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(top_up_ctx, top_up)?;
+    }
+    msg!(
+        "Growing {} {} -> {} bytes",
+        account.key,
+        account.data_len(),
+        new_len
+    );
+    account.realloc(new_len, true)?;
+    Ok(true)
+}
+
+/// Lamports the reward vault can pay out without dropping below rent exemption
+fn available_vault_lamports(reward_vault: &AccountInfo) -> Result<u64> {
+    let rent_floor = Rent::get()?.minimum_balance(reward_vault.data_len());
+    Ok(reward_vault.lamports().saturating_sub(rent_floor))
+}
+
+/// Transfer `amount` lamports out of the reward vault PDA
+fn pay_from_reward_vault<'info>(
+    program_id: &Pubkey,
+    mint_key: &Pubkey,
+    reward_vault: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let ix = anchor_lang::solana_program::system_instruction::transfer(
+        reward_vault.key,
+        destination.key,
+        amount,
+    );
+    let (_, reward_vault_bump) = Pubkey::find_program_address(
+        &[b"reward_vault", program_id.as_ref(), mint_key.as_ref()],
+        program_id,
+    );
+    let reward_vault_seeds = &[
+        b"reward_vault",
+        program_id.as_ref(),
+        mint_key.as_ref(),
+        &[reward_vault_bump],
+    ];
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        &[
+            reward_vault.clone(),
+            destination.clone(),
+            system_program.clone(),
+        ],
+        &[reward_vault_seeds],
+    )?;
+    Ok(())
+}
+
+/// Transfer `amount` tokens out of a vault owned by the vault authority PDA
+fn transfer_from_vault<'info>(
+    program_id: &Pubkey,
+    mint_key: &Pubkey,
+    token_program: &Program<'info, anchor_spl::token::Token>,
+    vault: &Account<'info, anchor_spl::token::TokenAccount>,
+    destination: &AccountInfo<'info>,
+    vault_authority: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let (_, vault_authority_bump) = Pubkey::find_program_address(
+        &[b"vault_authority", program_id.as_ref(), mint_key.as_ref()],
+        program_id,
+    );
+    let vault_authority_seeds = &[
+        b"vault_authority",
+        program_id.as_ref(),
+        mint_key.as_ref(),
+        &[vault_authority_bump],
+    ];
+    let signer_seeds = &[&vault_authority_seeds[..]];
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::Transfer {
+            from: vault.to_account_info(),
+            to: destination.clone(),
+            authority: vault_authority.clone(),
+        },
+        signer_seeds,
+    );
+    token::transfer(transfer_ctx, amount)
+}
+
+/// Load and validate a (user_info, user_wallet, user_token_account) triple
+/// passed through `remaining_accounts`
+fn load_holder<'info>(
+    program_id: &Pubkey,
+    mint_key: &Pubkey,
+    user_info: &AccountInfo<'info>,
+    user_wallet: &AccountInfo<'info>,
+    user_token_account: &AccountInfo<'info>,
+) -> Result<(
+    Account<'info, state::UserInfo>,
+    Account<'info, anchor_spl::token::TokenAccount>,
+)> {
+    let (expected_user_info, _) = Pubkey::find_program_address(
+        &[
+            b"user",
+            program_id.as_ref(),
+            user_wallet.key.as_ref(),
+            mint_key.as_ref(),
+        ],
+        program_id,
+    );
+    require!(
+        *user_info.key == expected_user_info && user_info.is_writable && user_wallet.is_writable,
+        TaxRewardError::InvalidInstruction
+    );
+
+    let token_account = Account::<anchor_spl::token::TokenAccount>::try_from(user_token_account)?;
+    require!(
+        token_account.mint == *mint_key && token_account.owner == *user_wallet.key,
+        TaxRewardError::InvalidTokenAccount
+    );
+
+    Ok((Account::try_from(user_info)?, token_account))
+}
+
+/// Settle owed rewards at the old snapshot, then re-snapshot from the live
+/// balance. Payouts go to `user_wallet`, so holders with a custom claim
+/// destination have them carried in `unpaid_rewards` instead. Returns the
+/// lamports paid.
+#[allow(clippy::too_many_arguments)]
+fn sync_holder<'info>(
+    program_id: &Pubkey,
+    mint_key: &Pubkey,
+    cfg: &state::Config,
+    global: &mut state::GlobalState,
+    user_info: &mut state::UserInfo,
+    user_token_account: &anchor_spl::token::TokenAccount,
+    reward_vault: &AccountInfo<'info>,
+    user_wallet: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u64> {
+    // Validate the re-snapshot before any lamports move
+    let old_snapshot = user_info.balance_snapshot;
+    let weight = cfg.reward_weight(user_info, user_token_account.amount);
+    global
+        .eligible_supply_after(old_snapshot, weight)
+        .ok_or(TaxRewardError::Overflow)?;
+
+    let owed = pay_owed_rewards(
+        program_id,
+        mint_key,
+        global,
+        user_info,
+        user_wallet.key,
+        reward_vault,
+        user_wallet,
+        system_program,
+    )?;
+    update_snapshot(global, user_info, weight)?;
+    msg!(
+        "Synced {}: paid {}, snapshot {} -> {}",
+        user_wallet.key,
+        owed,
+        old_snapshot,
+        user_info.balance_snapshot
+    );
+    Ok(owed)
+}
+
+/// Record a holder-driven interaction, counting first-time holders
+fn touch_holder(
+    global: &mut state::GlobalState,
+    user_info: &mut state::UserInfo,
+    now: i64,
+) -> Result<()> {
+    if user_info.touch(now) {
+        global.register_holder().ok_or(TaxRewardError::Overflow)?;
+    }
+    Ok(())
+}
+
+/// Payout bookkeeping shared by every path that settles owed rewards
+fn record_claim(
+    global: &mut state::GlobalState,
+    user_info: &mut state::UserInfo,
+    lamports: u64,
+) -> Result<()> {
+    user_info
+        .record_claim(lamports)
+        .ok_or(TaxRewardError::Overflow)?;
+    global
+        .record_claim(lamports)
+        .ok_or(TaxRewardError::Overflow)?;
+    Ok(())
+}
+
+/// Replace a user's balance snapshot, keeping `eligible_supply` in sync
+fn update_snapshot(
+    global: &mut state::GlobalState,
+    user_info: &mut state::UserInfo,
+    new_balance: u64,
+) -> Result<()> {
+    global
+        .update_snapshot(user_info, new_balance)
+        .ok_or(TaxRewardError::Overflow)?;
+    Ok(())
 }
 
 /// Helper function to calculate owed rewards for a user
@@ -332,16 +1881,10 @@ fn calculate_owed_rewards(
     global_cum_reward_per_token: u128,
     user_last_cum: u128,
 ) -> Result<u64> {
-    let owed_u128 = (user_balance_snapshot as u128)
-        .checked_mul(
-            global_cum_reward_per_token
-                .checked_sub(user_last_cum)
-                .ok_or(TaxRewardError::Overflow)?,
-        )
-        .ok_or(TaxRewardError::Overflow)?
-        .checked_div(1_000_000_000_000_000_000u128) // SCALE constant
-        .ok_or(TaxRewardError::Overflow)?;
-
-    Ok(owed_u128 as u64)
+    math::calculate_owed_rewards(
+        user_balance_snapshot,
+        global_cum_reward_per_token,
+        user_last_cum,
+    )
+    .ok_or_else(|| TaxRewardError::Overflow.into())
 }
-
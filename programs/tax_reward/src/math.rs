@@ -0,0 +1,116 @@
+//! Fixed-point helpers shared by the tax and reward accounting paths
+//!
+//! Reward math multiplies 64-bit balances by 128-bit cumulative values scaled
+//! by 1e18, which can exceed `u128` for high-supply or high-decimals mints.
+//! All reward products are therefore computed in 256 bits and only narrowed
+//! once divided back down.
+use crate::state::TaxRounding;
+
+// The macro expansion trips several style lints that are not ours to fix
+#[allow(clippy::all)]
+mod u256 {
+    uint::construct_uint! {
+        /// 256-bit unsigned integer for intermediate reward products
+        pub struct U256(4);
+    }
+}
+
+pub use u256::U256;
+
+/// Denominator for basis-point rates
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Scale factor for cumulative reward accounting (1e18)
+pub const SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Compute the tax owed on `amount` at `tax_rate_bps`.
+///
+/// `carry` is the fractional tax (in 1/10_000 token units) left over from
+/// previous trades. It is folded into this trade before rounding, and the new
+/// carry is returned alongside the tax, so the tax collected across many
+/// trades tracks the nominal rate regardless of the rounding policy.
+/// The tax never exceeds `amount`; anything clamped stays in the carry.
+pub fn calculate_tax(
+    amount: u64,
+    tax_rate_bps: u16,
+    rounding: TaxRounding,
+    carry: i64,
+) -> Option<(u64, i64)> {
+    let denom = BPS_DENOMINATOR as i128;
+    let nominal = (amount as i128)
+        .checked_mul(tax_rate_bps as i128)?
+        .checked_add(carry as i128)?;
+
+    let floor = nominal.div_euclid(denom);
+    let rem = nominal.rem_euclid(denom);
+    let rounded = match rounding {
+        TaxRounding::Floor => floor,
+        TaxRounding::Ceil if rem > 0 => floor + 1,
+        TaxRounding::Ceil => floor,
+        TaxRounding::Bankers if rem * 2 > denom => floor + 1,
+        TaxRounding::Bankers if rem * 2 == denom => floor + (floor & 1),
+        TaxRounding::Bankers => floor,
+    };
+
+    let tax = rounded.clamp(0, amount as i128);
+    let new_carry = nominal.checked_sub(tax.checked_mul(denom)?)?;
+    Some((tax as u64, i64::try_from(new_carry).ok()?))
+}
+
+/// Compute `a * b / denom` with a 256-bit intermediate, rounding down.
+/// Returns `None` if `denom` is zero or the result does not fit in `u128`.
+pub fn mul_div(a: u128, b: u128, denom: u128) -> Option<u128> {
+    if denom == 0 {
+        return None;
+    }
+    let result = U256::from(a).checked_mul(U256::from(b))? / U256::from(denom);
+    narrow(result)
+}
+
+/// Apply a basis-point rate to `amount`, rounding down. Rates above 100%
+/// (boost multipliers) are allowed; returns `None` if the result overflows.
+pub fn apply_bps(amount: u64, bps: u16) -> Option<u64> {
+    let scaled = (amount as u128).checked_mul(bps as u128)? / BPS_DENOMINATOR as u128;
+    u64::try_from(scaled).ok()
+}
+
+/// Convert `lamports` of new rewards into a `cum_reward_per_token` increment.
+///
+/// `dust` is the scaled remainder (lamports * SCALE) that previous
+/// distributions could not spread over `supply`. It is added back before
+/// dividing, and the new remainder is returned, so that
+/// `delta_cum * supply + new_dust == lamports * SCALE + dust` always holds.
+/// With zero `supply` everything is carried until there is a denominator.
+pub fn reward_per_token_delta(lamports: u64, supply: u64, dust: u128) -> Option<(u128, u128)> {
+    scaled_reward_per_token_delta((lamports as u128).checked_mul(SCALE)?, supply, dust)
+}
+
+/// Same as `reward_per_token_delta` for an amount already scaled by SCALE,
+/// as produced by a streaming `reward_rate`
+pub fn scaled_reward_per_token_delta(
+    scaled_lamports: u128,
+    supply: u64,
+    dust: u128,
+) -> Option<(u128, u128)> {
+    let scaled = U256::from(scaled_lamports).checked_add(U256::from(dust))?;
+    if supply == 0 {
+        return Some((0, narrow(scaled)?));
+    }
+    let (delta_cum, dust) = scaled.div_mod(U256::from(supply));
+    Some((narrow(delta_cum)?, narrow(dust)?))
+}
+
+/// Lamports owed to `balance` tokens for the cumulative increase since
+/// `last_cum`. Returns `None` if `last_cum` is ahead of `cum` or the owed
+/// amount does not fit in a `u64`.
+pub fn calculate_owed_rewards(balance: u64, cum: u128, last_cum: u128) -> Option<u64> {
+    let owed = mul_div(balance as u128, cum.checked_sub(last_cum)?, SCALE)?;
+    u64::try_from(owed).ok()
+}
+
+fn narrow(value: U256) -> Option<u128> {
+    if value.bits() > 128 {
+        return None;
+    }
+    Some(value.as_u128())
+}
@@ -0,0 +1,35 @@
+//! Merkle proofs for epoch-based reward distributions
+//!
+//! Leaves commit to `(index, wallet, lamports)`. Leaf and node hashes use
+//! distinct prefixes so an inner node can never be replayed as a leaf, and
+//! sibling pairs are sorted before hashing so proofs need no direction bits.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak::hashv;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Hash of a single distribution entry
+pub fn leaf_hash(index: u32, wallet: &Pubkey, lamports: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        wallet.as_ref(),
+        &lamports.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hash of two sibling nodes, order-independent
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+/// True if `proof` links `leaf` to `root`
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| node_hash(&node, sibling));
+    computed == *root
+}
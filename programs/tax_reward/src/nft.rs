@@ -0,0 +1,149 @@
+//! Minimal Metaplex metadata reader for NFT-collection-gated tax discounts
+//!
+//! Only the fields needed to verify collection membership are decoded, so the
+//! program does not need to depend on the full Metaplex crate. The layout
+//! follows `mpl_token_metadata::state::Metadata` (borsh encoded):
+//!
+//! key, update_authority, mint, name, symbol, uri, seller_fee_basis_points,
+//! creators, primary_sale_happened, is_mutable, edition_nonce,
+//! token_standard, collection, ...
+use anchor_lang::prelude::*;
+
+/// Metaplex Token Metadata program
+pub mod token_metadata_program {
+    use anchor_lang::declare_id;
+    declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+pub use token_metadata_program::ID as TOKEN_METADATA_PROGRAM_ID;
+
+/// `Key::MetadataV1` discriminator
+pub const METADATA_V1_KEY: u8 = 4;
+
+// Creator = address (32) + verified (1) + share (1)
+const CREATOR_LEN: usize = 32 + 1 + 1;
+
+/// Collection reference stored in a metadata account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MetadataCollection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+/// Fields of a metadata account relevant to collection gating
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollectionMetadata {
+    pub mint: Pubkey,
+    pub collection: Option<MetadataCollection>,
+}
+
+/// Metadata PDA for `nft_mint` under the Metaplex program
+pub fn metadata_address(nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            nft_mint.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Decode the mint and collection from raw metadata account data.
+/// Returns `None` if the data is not a well-formed `MetadataV1` account.
+pub fn parse_collection_metadata(data: &[u8]) -> Option<CollectionMetadata> {
+    let mut reader = Reader { data, pos: 0 };
+
+    if reader.u8()? != METADATA_V1_KEY {
+        return None;
+    }
+    reader.skip(32)?; // update_authority
+    let mint = reader.pubkey()?;
+    reader.string()?; // name
+    reader.string()?; // symbol
+    reader.string()?; // uri
+    reader.skip(2)?; // seller_fee_basis_points
+    if reader.option_tag()? {
+        let creators = reader.u32()? as usize;
+        reader.skip(creators.checked_mul(CREATOR_LEN)?)?;
+    }
+    reader.skip(2)?; // primary_sale_happened + is_mutable
+    if reader.option_tag()? {
+        reader.skip(1)?; // edition_nonce
+    }
+    if reader.option_tag()? {
+        reader.skip(1)?; // token_standard
+    }
+    let collection = if reader.option_tag()? {
+        Some(MetadataCollection {
+            verified: reader.u8()? != 0,
+            key: reader.pubkey()?,
+        })
+    } else {
+        None
+    };
+
+    Some(CollectionMetadata { mint, collection })
+}
+
+/// True if the metadata lists `collection` as its verified collection
+pub fn is_verified_member(metadata: &CollectionMetadata, collection: &Pubkey) -> bool {
+    matches!(
+        metadata.collection,
+        Some(MetadataCollection { verified: true, key }) if key == *collection
+    )
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let bytes = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        self.take(32)
+            .map(|b| Pubkey::new_from_array(b.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Option<()> {
+        let len = self.u32()? as usize;
+        self.skip(len)
+    }
+
+    // Missing trailing bytes decode as `None`, matching older metadata versions
+    fn option_tag(&mut self) -> Option<bool> {
+        match self.data.get(self.pos) {
+            None => Some(false),
+            Some(0) => {
+                self.pos += 1;
+                Some(false)
+            }
+            Some(1) => {
+                self.pos += 1;
+                Some(true)
+            }
+            Some(_) => None,
+        }
+    }
+}
@@ -2,38 +2,460 @@ use anchor_lang::prelude::*;
 
 /// Holds tax rates, owner, DEX config, paused flag.
 #[account]
+#[derive(Default)]
 pub struct Config {
     pub tax_rate_bps: u16,
     pub owner: Pubkey,
     pub dex_program: Pubkey,
     pub paused: bool,
+    /// Verified Metaplex collection whose holders get a tax discount (default = disabled)
+    pub nft_discount_collection: Pubkey,
+    /// Basis points subtracted from the tax rate for collection holders
+    pub nft_discount_bps: u16,
+    /// Rounding applied to the per-trade tax amount
+    pub tax_rounding: TaxRounding,
+    /// Snapshots below this balance earn nothing and count as zero supply
+    pub min_reward_balance: u64,
+    /// Seconds over which new rewards are streamed; 0 distributes instantly
+    pub reward_duration: i64,
+    /// Key allowed to publish epoch Merkle roots besides the owner
+    pub epoch_publisher: Pubkey,
+    /// Seconds without interaction after which pending rewards may be
+    /// recycled; 0 disables expiry
+    pub reward_expiry_secs: i64,
+    /// Rewards accrue only on tokens staked in the stake vault, not on
+    /// wallet balances
+    pub staking_mode: bool,
+    /// Lock durations and their reward multipliers; unused slots are zeroed
+    pub lock_tiers: [LockTier; MAX_LOCK_TIERS],
+    /// Share of a lock forfeited when unlocking early; swapped to SOL and
+    /// deposited as rewards for the remaining holders
+    pub early_unlock_penalty_bps: u16,
 }
 
 impl Config {
-    // u16 + Pubkey + Pubkey + bool
-    pub const LEN: usize = 2 + 32 + 32 + 1;
+    // u16 + Pubkey + Pubkey + bool + Pubkey + u16 + TaxRounding + u64 + i64 + Pubkey + i64 + bool
+    // + [LockTier; 4] + u16
+    pub const LEN: usize =
+        2 + 32 + 32 + 1 + 32 + 2 + 1 + 8 + 8 + 32 + 8 + 1 + LockTier::LEN * MAX_LOCK_TIERS + 2;
+
+    /// Balance that earns rewards: the staked amount in staking mode,
+    /// otherwise the wallet balance, plus boosted lock weight; zero for
+    /// excluded wallets and for weights below `min_reward_balance`
+    pub fn reward_weight(&self, user_info: &UserInfo, balance: u64) -> u64 {
+        let balance = if self.staking_mode {
+            user_info.staked_amount
+        } else {
+            balance
+        };
+        let balance = balance.saturating_add(user_info.locked_weight);
+        if user_info.excluded || balance < self.min_reward_balance {
+            0
+        } else {
+            balance
+        }
+    }
+}
+
+pub const MAX_LOCK_TIERS: usize = 4;
+
+/// A lock duration and the reward weight multiplier it earns (10_000 = 1x)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockTier {
+    pub duration_secs: i64,
+    pub multiplier_bps: u16,
+}
+
+impl LockTier {
+    // i64 + u16
+    pub const LEN: usize = 8 + 2;
+
+    pub fn is_enabled(&self) -> bool {
+        self.duration_secs > 0
+    }
+}
+
+/// Tokens locked in the stake vault until `unlock_ts` for boosted rewards.
+/// One PDA per (user, lock_id).
+#[account]
+pub struct LockPosition {
+    pub owner: Pubkey,
+    pub lock_id: u64,
+    pub amount: u64,
+    /// `amount` times the tier multiplier, added to `UserInfo::locked_weight`
+    pub weight: u64,
+    pub start_ts: i64,
+    pub unlock_ts: i64,
+}
+
+impl LockPosition {
+    // Pubkey + u64 + u64 + u64 + i64 + i64
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8;
+
+    pub fn is_unlocked(&self, now: i64) -> bool {
+        now >= self.unlock_ts
+    }
+}
+
+/// Rounding policy for `amount_in * tax_rate_bps / 10_000`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TaxRounding {
+    #[default]
+    Floor,
+    Ceil,
+    /// Round half to even
+    Bankers,
 }
 
 /// Tracks total supply and cumulative rewards per token (scaled by 1e18).
+///
+/// Rewards are spread over `eligible_supply`, the sum of every registered
+/// `UserInfo::balance_snapshot`, rather than the raw mint supply.
 #[account]
+#[derive(Default)]
 pub struct GlobalState {
     pub total_supply: u64,
     pub cum_reward_per_token: u128,
+    /// Fractional tax carried between trades, in 1/10_000 token units
+    pub tax_dust: i64,
+    /// Scaled reward remainder (lamports * 1e18) not yet spread over supply
+    pub reward_dust: u128,
+    /// Sum of all registered balance snapshots; the reward denominator
+    pub eligible_supply: u64,
+    /// Streaming emission rate in lamports per second, scaled by 1e18
+    pub reward_rate: u128,
+    /// Unix timestamp at which the current stream ends
+    pub period_finish: i64,
+    /// Unix timestamp up to which the stream has been accrued
+    pub last_update_time: i64,
+    /// Lifetime tokens collected as tax
+    pub total_tokens_taxed: u64,
+    /// Lifetime lamports received from taxed swaps
+    pub total_sol_swapped: u64,
+    /// Lifetime reward lamports paid out
+    pub total_sol_claimed: u64,
+    pub swap_count: u64,
+    /// Payouts of a non-zero amount
+    pub claim_count: u64,
+    /// Holders whose first interaction has been recorded
+    pub unique_holders: u64,
+    /// Largest single swap, in lamports received
+    pub largest_swap: u64,
+    /// Reward lamports deposited but not yet paid out; what `reward_vault`
+    /// must hold above rent to stay solvent
+    pub outstanding_liability: u64,
+    /// `cum_reward_per_token` when `migrate_state` upgraded this account;
+    /// pre-upgrade snapshots are settled up to here and no further
+    pub legacy_cum: u128,
 }
 
 impl GlobalState {
-    // u64 + u128
-    pub const LEN: usize = 8 + 16;
+    // u64 + u128 + i64 + u128 + u64 + u128 + i64 + i64 + 7 * u64 + u64 + u128
+    pub const LEN: usize = 8 + 16 + 8 + 16 + 8 + 16 + 8 + 8 + 7 * 8 + 8 + 16;
+
+    pub fn record_swap(&mut self, tokens_taxed: u64, sol_swapped: u64) -> Option<()> {
+        self.total_tokens_taxed = self.total_tokens_taxed.checked_add(tokens_taxed)?;
+        self.total_sol_swapped = self.total_sol_swapped.checked_add(sol_swapped)?;
+        self.swap_count = self.swap_count.checked_add(1)?;
+        self.largest_swap = self.largest_swap.max(sol_swapped);
+        Some(())
+    }
+
+    pub fn record_claim(&mut self, lamports: u64) -> Option<()> {
+        if lamports > 0 {
+            self.total_sol_claimed = self.total_sol_claimed.checked_add(lamports)?;
+            self.claim_count = self.claim_count.checked_add(1)?;
+            // Saturating: deposits made before liability was tracked are not counted
+            self.outstanding_liability = self.outstanding_liability.saturating_sub(lamports);
+        }
+        Some(())
+    }
+
+    pub fn register_holder(&mut self) -> Option<()> {
+        self.unique_holders = self.unique_holders.checked_add(1)?;
+        Some(())
+    }
+
+    /// `eligible_supply` once a snapshot of `old` is replaced by `new`
+    pub fn eligible_supply_after(&self, old: u64, new: u64) -> Option<u64> {
+        self.eligible_supply.checked_sub(old)?.checked_add(new)
+    }
+
+    /// Replace a user's balance snapshot, keeping `eligible_supply` in sync
+    pub fn update_snapshot(&mut self, user_info: &mut UserInfo, new_balance: u64) -> Option<()> {
+        self.eligible_supply =
+            self.eligible_supply_after(user_info.balance_snapshot, new_balance)?;
+        user_info.balance_snapshot = new_balance;
+        Some(())
+    }
+
+    /// Emit the stream up to `now` (capped at `period_finish`)
+    pub fn accrue(&mut self, now: i64) -> Option<()> {
+        let until = now.min(self.period_finish);
+        if self.reward_rate > 0 && until > self.last_update_time {
+            let elapsed = (until - self.last_update_time) as u128;
+            self.distribute_scaled(self.reward_rate.checked_mul(elapsed)?)?;
+        }
+        self.last_update_time = self.last_update_time.max(now);
+        Some(())
+    }
+
+    /// Add new reward lamports. With `duration <= 0` they are distributed
+    /// immediately. Otherwise they are streamed: a deposit at least as large
+    /// as what is left of the current stream starts a new one ending
+    /// `duration` from now, and a smaller one is folded into the current
+    /// stream without moving `period_finish`, so small deposits can never
+    /// stretch a stream out. Callers must have accrued up to `now` first.
+    pub fn deposit(&mut self, lamports: u64, duration: i64, now: i64) -> Option<()> {
+        if lamports == 0 {
+            return Some(());
+        }
+        self.outstanding_liability = self.outstanding_liability.checked_add(lamports)?;
+        let scaled = (lamports as u128).checked_mul(crate::math::SCALE)?;
+        if duration <= 0 {
+            return self.distribute_scaled(scaled);
+        }
+
+        let remaining = if now < self.period_finish {
+            self.period_finish - now
+        } else {
+            0
+        };
+        let leftover = self.reward_rate.checked_mul(remaining as u128)?;
+        let total = scaled.checked_add(leftover)?;
+        let stream_secs = if remaining > 0 && scaled < leftover {
+            remaining
+        } else {
+            self.period_finish = now.checked_add(duration)?;
+            duration
+        };
+
+        // The rate's division remainder joins the dust so nothing is lost
+        self.reward_rate = total / stream_secs as u128;
+        self.reward_dust = self.reward_dust.checked_add(total % stream_secs as u128)?;
+        self.last_update_time = now;
+        Some(())
+    }
+
+    /// Spread scaled lamports over `eligible_supply`, carrying the remainder
+    pub fn distribute_scaled(&mut self, scaled: u128) -> Option<()> {
+        let (delta_cum, reward_dust) = crate::math::scaled_reward_per_token_delta(
+            scaled,
+            self.eligible_supply,
+            self.reward_dust,
+        )?;
+        self.reward_dust = reward_dust;
+        self.cum_reward_per_token = self.cum_reward_per_token.checked_add(delta_cum)?;
+        Some(())
+    }
 }
 
 /// User-specific info for reward pulls.
 #[account]
+#[derive(Default)]
 pub struct UserInfo {
     pub last_cum: u128,
     pub balance_snapshot: u64,
+    /// Owner-managed exclusion (pools, vaults, burn addresses); snapshot is pinned to zero
+    pub excluded: bool,
+    /// Where claims pay out (cold wallet, multisig); `None` pays the wallet
+    pub claim_destination: Option<Pubkey>,
+    /// Key allowed to trigger claims without the owner's signature
+    pub claim_delegate: Option<Pubkey>,
+    /// Last swap, claim or settings change by the holder; drives reward expiry
+    pub last_interaction_ts: i64,
+    /// Tokens held for the user in the stake vault
+    pub staked_amount: u64,
+    /// Sum of the boosted weights of the user's open lock positions
+    pub locked_weight: u64,
+    /// Slot of the last explicit claim (0 = never claimed)
+    pub last_claim_slot: u64,
+    /// Lifetime tokens paid in tax
+    pub total_tax_paid: u64,
+    /// Lifetime reward lamports paid out
+    pub total_claimed: u64,
+    pub taxed_trade_count: u64,
+    pub first_interaction_ts: i64,
+    /// Owed lamports the reward vault could not cover without dropping
+    /// below rent exemption; paid first on the next settlement
+    pub unpaid_rewards: u64,
 }
 
 impl UserInfo {
-    // u128 + u64
-    pub const LEN: usize = 16 + 8;
-}
\ No newline at end of file
+    // u128 + u64 + bool + Option<Pubkey> + Option<Pubkey> + i64 + u64 + u64 + u64
+    // + u64 + u64 + u64 + i64 + u64
+    pub const LEN: usize = 16 + 8 + 1 + 33 + 33 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8;
+
+    /// Record a holder-driven interaction at `now`. Returns true for the
+    /// holder's first interaction.
+    pub fn touch(&mut self, now: i64) -> bool {
+        let first = self.first_interaction_ts == 0;
+        if first {
+            self.first_interaction_ts = now;
+        }
+        self.last_interaction_ts = now;
+        first
+    }
+
+    pub fn record_claim(&mut self, lamports: u64) -> Option<()> {
+        self.total_claimed = self.total_claimed.checked_add(lamports)?;
+        Some(())
+    }
+
+    pub fn record_taxed_trade(&mut self, tax_amount: u64) -> Option<()> {
+        self.total_tax_paid = self.total_tax_paid.checked_add(tax_amount)?;
+        self.taxed_trade_count = self.taxed_trade_count.checked_add(1)?;
+        Some(())
+    }
+
+    /// Settle rewards owed at the current snapshot up to `cum`, plus any
+    /// earlier carry-over, against `available` lamports. Whatever cannot be
+    /// paid stays in `unpaid_rewards`. Returns (paid, due).
+    pub fn settle(&mut self, cum: u128, available: u64) -> Option<(u64, u64)> {
+        let owed = crate::math::calculate_owed_rewards(self.balance_snapshot, cum, self.last_cum)?;
+        self.last_cum = cum;
+        let due = owed.checked_add(self.unpaid_rewards)?;
+        let paid = due.min(available);
+        self.unpaid_rewards = due - paid;
+        Some((paid, due))
+    }
+
+    /// Settle a pre-upgrade account, whose snapshot never counted towards
+    /// `eligible_supply`, into `unpaid_rewards` up to `legacy_cum` only:
+    /// later distributions were spread over holders that excluded it. The
+    /// snapshot is zeroed and `last_cum` moved to the live `cum`.
+    pub fn retire_legacy_snapshot(&mut self, legacy_cum: u128, cum: u128) -> Option<()> {
+        self.settle(legacy_cum, 0)?;
+        self.balance_snapshot = 0;
+        self.last_cum = cum;
+        Some(())
+    }
+
+    /// True while tokens are staked or locked for the user
+    pub fn holds_stake(&self) -> bool {
+        self.staked_amount > 0 || self.locked_weight > 0
+    }
+
+    /// True once the holder has been inactive for `expiry_secs` (0 = never)
+    pub fn is_stale(&self, now: i64, expiry_secs: i64) -> bool {
+        expiry_secs > 0 && now.saturating_sub(self.last_interaction_ts) >= expiry_secs
+    }
+}
+
+/// A scheduled tax override window, active for `start_ts <= now < end_ts`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TaxWindow {
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub tax_rate_bps: u16,
+}
+
+impl TaxWindow {
+    // i64 + i64 + u16
+    pub const LEN: usize = 8 + 8 + 2;
+
+    pub fn contains(&self, now: i64) -> bool {
+        self.start_ts <= now && now < self.end_ts
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.end_ts <= now
+    }
+}
+
+/// Scheduled tax holidays; lowers `Config::tax_rate_bps` while a window is active.
+#[account]
+pub struct TaxSchedule {
+    pub windows: Vec<TaxWindow>,
+}
+
+impl TaxSchedule {
+    pub const MAX_WINDOWS: usize = 8;
+    // vec length prefix + MAX_WINDOWS * TaxWindow
+    pub const LEN: usize = 4 + Self::MAX_WINDOWS * TaxWindow::LEN;
+
+    /// Override rate of the first window containing `now`, if any.
+    pub fn active_rate(&self, now: i64) -> Option<u16> {
+        self.windows
+            .iter()
+            .find(|w| w.contains(now))
+            .map(|w| w.tax_rate_bps)
+    }
+
+    /// Drop windows that have ended; returns how many were removed.
+    pub fn prune_expired(&mut self, now: i64) -> usize {
+        let before = self.windows.len();
+        self.windows.retain(|w| !w.is_expired(now));
+        before - self.windows.len()
+    }
+}
+
+/// A published epoch distribution: holders claim `(index, wallet, lamports)`
+/// leaves of `merkle_root` from the lamports funded into this account.
+#[account]
+pub struct Epoch {
+    pub epoch_id: u64,
+    pub merkle_root: [u8; 32],
+    pub total_lamports: u64,
+    pub claimed_lamports: u64,
+    pub leaf_count: u32,
+    /// One bit per leaf index, set once claimed
+    pub claimed_bitmap: Vec<u8>,
+}
+
+impl Epoch {
+    /// Keeps the bitmap within the 10KiB limit for accounts created by CPI
+    pub const MAX_LEAVES: u32 = 65_536;
+    // u64 + [u8; 32] + u64 + u64 + u32 + vec length prefix
+    pub const BASE_LEN: usize = 8 + 32 + 8 + 8 + 4 + 4;
+
+    pub fn bitmap_len(leaf_count: u32) -> usize {
+        (leaf_count as usize + 7) / 8
+    }
+
+    pub fn space(leaf_count: u32) -> usize {
+        Self::BASE_LEN + Self::bitmap_len(leaf_count)
+    }
+
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed_bitmap[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u32) {
+        self.claimed_bitmap[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+/// Outcome of one `batch_claim` entry
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchClaimStatus {
+    Paid,
+    NothingOwed,
+    /// PDA derivation, ownership or token account checks failed
+    InvalidAccounts,
+    /// The holder set a claim destination other than their wallet
+    DestinationMismatch,
+}
+
+/// Per-entry `batch_claim` result, returned in entry order via return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchClaimResult {
+    pub status: BatchClaimStatus,
+    pub amount: u64,
+}
+
+impl BatchClaimResult {
+    pub fn skipped(status: BatchClaimStatus) -> Self {
+        Self { status, amount: 0 }
+    }
+}
+
+/// `get_pending_rewards` return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PendingRewards {
+    /// Lamports claimable now, including any not yet accrued stream
+    pub owed: u64,
+    pub balance_snapshot: u64,
+    pub last_claim_slot: u64,
+    pub token_balance: u64,
+}
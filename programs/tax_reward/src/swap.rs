@@ -2,13 +2,13 @@
 //!
 //! This module provides the core swap functionality for the tax & reward mechanism.
 //! It supports multiple swap strategies:
-//! 
+//!
 //! 1. **Mock Implementation** (default): For development and testing
 //! 2. **Jupiter Integration** (production): Primary DEX for swaps
 //! 3. **Serum Integration** (production): Fallback DEX when Jupiter fails
 //!
 //! ## Implementation Notes
-//! 
+//!
 //! - All swaps use the vault authority PDA for signing
 //! - Slippage protection is enforced at the program level
 //! - Comprehensive error handling and logging for debugging
@@ -19,19 +19,19 @@
 //! - Vault authority seeds must be properly secured
 //! - Input validation prevents malicious swap parameters
 //! - Balance checks ensure atomic swap operations
+use crate::error::TaxRewardError;
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{
     account_info::AccountInfo,
     entrypoint::ProgramResult,
-    pubkey::Pubkey,
-    msg,
-    program::{invoke_signed, invoke},
     instruction::{AccountMeta, Instruction},
+    msg,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
+    pubkey::Pubkey,
     system_instruction,
 };
-use anchor_spl::token::{self, TokenAccount, Token};
-use crate::error::TaxRewardError;
+use anchor_spl::token::{self, Token, TokenAccount};
 
 /// Swap collected tokens for SOL using external DEX (primary: Jupiter, fallback: Serum)
 pub fn swap_tokens_for_sol(
@@ -40,14 +40,18 @@ pub fn swap_tokens_for_sol(
     token_amount: u64,
     min_amount_out: u64,
 ) -> ProgramResult {
-    msg!("Starting swap of {} tokens for minimum {} SOL", token_amount, min_amount_out);
-    
+    msg!(
+        "Starting swap of {} tokens for minimum {} SOL",
+        token_amount,
+        min_amount_out
+    );
+
     // Validate inputs
     if token_amount == 0 {
         msg!("Invalid token amount: cannot swap 0 tokens");
         return Err(ProgramError::InvalidArgument);
     }
-    
+
     // For now, use a mock implementation that simulates the swap
     // In production, this would integrate with real DEX
     match mock_swap_for_development(program_id, accounts, token_amount, min_amount_out) {
@@ -70,17 +74,21 @@ fn mock_swap_for_development(
     token_amount: u64,
     min_amount_out: u64,
 ) -> ProgramResult {
-    msg!(" MOCK SWAP: Converting {} tokens to ~{} SOL (for development)", token_amount, min_amount_out);
-    
+    msg!(
+        " MOCK SWAP: Converting {} tokens to ~{} SOL (for development)",
+        token_amount,
+        min_amount_out
+    );
+
     // In a real implementation, this would:
     // 1. Transfer tokens from token_vault to DEX
     // 2. Execute swap instruction
     // 3. Receive SOL in reward_vault
-    
+
     // For mock, we simulate receiving min_amount_out SOL
     // The reward vault should receive SOL from somewhere (e.g., test setup)
     msg!("Mock swap completed - reward vault should be credited externally in tests");
-    
+
     Ok(())
 }
 
@@ -97,12 +105,12 @@ fn jupiter_swap_with_vault_authority(
     min_amount_out: u64,
 ) -> ProgramResult {
     msg!("Jupiter integration template - not yet implemented");
-    
+
     // Step 1: Validate accounts
     // - Ensure token_vault has sufficient balance
     // - Validate mint matches token_vault mint
     // - Ensure vault_authority is correct PDA
-    
+
     // Step 2: Prepare vault authority seeds for signing
     let mint_key = mint.key();
     let vault_authority_seeds = &[
@@ -111,7 +119,7 @@ fn jupiter_swap_with_vault_authority(
         mint_key.as_ref(),
         // &[vault_authority_bump], // Need bump from context
     ];
-    
+
     // Step 3: Create Jupiter swap instruction
     // This would use Jupiter's Rust SDK or manual instruction building:
     /*
@@ -128,7 +136,7 @@ fn jupiter_swap_with_vault_authority(
         // Additional Jupiter-specific parameters
     )?;
     */
-    
+
     // Step 4: Execute swap with vault authority signature
     /*
     invoke_signed(
@@ -143,16 +151,16 @@ fn jupiter_swap_with_vault_authority(
         &[vault_authority_seeds],
     )?;
     */
-    
+
     // Step 5: Verify swap results
     // - Check token_vault balance decreased by expected amount
     // - Check reward_vault balance increased by at least min_amount_out
-    
+
     msg!("Jupiter swap would execute here with proper implementation");
     Err(ProgramError::Custom(404)) // Not implemented
 }
 
-// TODO: Implement real Serum integration  
+// TODO: Implement real Serum integration
 #[allow(dead_code)]
 fn serum_swap_with_vault_authority(
     program_id: &Pubkey,
@@ -165,9 +173,9 @@ fn serum_swap_with_vault_authority(
 ) -> ProgramResult {
     // This would implement real Serum swap:
     // 1. Create Serum market orders
-    // 2. Use invoke_signed with vault_authority seeds  
+    // 2. Use invoke_signed with vault_authority seeds
     // 3. Execute trades and settle to reward_vault
-    
+
     msg!("Serum integration not yet implemented");
     Err(ProgramError::Custom(0)) // Placeholder error
 }
@@ -1,5 +1,6 @@
 use proptest::prelude::*;
 use solana_tax_reward::{
+    math,
     state::{Config, GlobalState, UserInfo},
     error::TaxRewardError,
 };
@@ -24,6 +25,11 @@ mod tax_reward_properties {
         0u128..1_000_000_000_000_000_000u128 // Up to 1e18
     }
 
+    /// Generate (global_cum, user_cum) pairs with user_cum <= global_cum
+    fn ordered_cum_values() -> impl Strategy<Value = (u128, u128)> {
+        reward_scale_values().prop_flat_map(|global_cum| (Just(global_cum), 0u128..=global_cum))
+    }
+
     proptest! {
         /// Property: Tax should never exceed the input amount
         #[test]
@@ -96,8 +102,7 @@ mod tax_reward_properties {
         /// Property: Rewards should be zero when balance is zero
         #[test]
         fn zero_balance_gives_zero_rewards(
-            global_cum in reward_scale_values(),
-            user_cum in 0u128..global_cum
+            (global_cum, user_cum) in ordered_cum_values()
         ) {
             let reward = calculate_reward(0, global_cum, user_cum);
             prop_assert_eq!(reward, 0, "Zero balance should give zero rewards");
@@ -107,8 +112,7 @@ mod tax_reward_properties {
         #[test]
         fn reward_increases_with_balance(
             balance in 1u64..1_000_000u64,
-            global_cum in reward_scale_values(),
-            user_cum in 0u128..global_cum
+            (global_cum, user_cum) in ordered_cum_values()
         ) {
             if global_cum > user_cum {
                 let reward1 = calculate_reward(balance, global_cum, user_cum);
@@ -132,6 +136,7 @@ mod tax_reward_properties {
                 owner: Pubkey::new_unique(),
                 dex_program: Pubkey::new_unique(),
                 paused: false,
+                ..Default::default()
             };
             
             let serialized_size = config.try_to_vec().unwrap().len();
@@ -141,15 +146,20 @@ mod tax_reward_properties {
             let global_state = GlobalState {
                 total_supply,
                 cum_reward_per_token: cum_reward,
+                ..Default::default()
             };
             
             let serialized_size = global_state.try_to_vec().unwrap().len();
             prop_assert_eq!(serialized_size, GlobalState::LEN, "GlobalState serialized size should match LEN constant");
 
             // Test UserInfo size consistency
+            // Options at their largest (Some) to match the allocated space
             let user_info = UserInfo {
                 last_cum,
                 balance_snapshot,
+                claim_destination: Some(Pubkey::new_unique()),
+                claim_delegate: Some(Pubkey::new_unique()),
+                ..Default::default()
             };
             
             let serialized_size = user_info.try_to_vec().unwrap().len();
@@ -292,6 +302,7 @@ mod edge_case_tests {
         let mut global_state = GlobalState {
             total_supply: 1_000_000,
             cum_reward_per_token: 0,
+            ..Default::default()
         };
 
         // Simulate reward distribution
@@ -303,6 +314,7 @@ mod edge_case_tests {
         let mut user_info = UserInfo {
             last_cum: 0,
             balance_snapshot: 1000,
+            ..Default::default()
         };
 
         // User claims rewards
@@ -345,11 +357,13 @@ mod integration_tests {
         let mut global_state = GlobalState {
             total_supply: 1_000_000,
             cum_reward_per_token: 0,
+            ..Default::default()
         };
         
         let mut user_info = UserInfo {
             last_cum: 0,
             balance_snapshot: 1000,
+            ..Default::default()
         };
         
         // 2. User performs a taxed swap
@@ -391,18 +405,21 @@ mod integration_tests {
         let mut global_state = GlobalState {
             total_supply: 10_000,
             cum_reward_per_token: 0,
+            ..Default::default()
         };
         
         // User 1: 1000 tokens
         let mut user1 = UserInfo {
             last_cum: 0,
             balance_snapshot: 1000,
+            ..Default::default()
         };
         
         // User 2: 4000 tokens  
         let mut user2 = UserInfo {
             last_cum: 0,
             balance_snapshot: 4000,
+            ..Default::default()
         };
         
         // Distribute 1000 lamports as rewards
@@ -446,6 +463,7 @@ mod integration_tests {
                 owner: Pubkey::new_unique(),
                 dex_program: Pubkey::new_unique(),
                 paused,
+                ..Default::default()
             };
             
             assert!(config.tax_rate_bps <= 10_000, "Tax rate should be valid");
@@ -477,6 +495,149 @@ mod integration_tests {
     }
 }
 
+/// Invariants of the reward dust carry-over
+mod reward_dust_properties {
+    use super::*;
+
+    proptest! {
+        /// Property: distributed plus carried always equals deposited, exactly
+        #[test]
+        fn distributed_plus_carried_equals_deposited(
+            supply in 1u64..1_000_000_000_000_000u64,
+            swaps in proptest::collection::vec(0u64..10_000_000_000u64, 1..100)
+        ) {
+            let mut global_state = GlobalState {
+                total_supply: supply,
+                cum_reward_per_token: 0,
+                ..Default::default()
+            };
+            let mut deposited = 0u128;
+
+            for lamports in swaps {
+                let (delta_cum, dust) = math::reward_per_token_delta(
+                    lamports,
+                    global_state.total_supply,
+                    global_state.reward_dust,
+                ).unwrap();
+                prop_assert!(dust < supply as u128, "Carried dust must be less than one unit per token");
+                global_state.cum_reward_per_token += delta_cum;
+                global_state.reward_dust = dust;
+                deposited += lamports as u128;
+            }
+
+            let distributed = global_state.cum_reward_per_token * supply as u128;
+            prop_assert_eq!(distributed + global_state.reward_dust, deposited * math::SCALE);
+
+            // A holder of the whole supply is owed everything but less than one lamport
+            let owed = distributed / math::SCALE;
+            prop_assert!(deposited - owed <= 1);
+        }
+
+        /// Property: with no supply every lamport is carried to the next distribution
+        #[test]
+        fn zero_supply_carries_everything(lamports in 0u64..u64::MAX, dust in 0u128..1_000_000u128) {
+            let (delta_cum, carried) = math::reward_per_token_delta(lamports, 0, dust).unwrap();
+            prop_assert_eq!(delta_cum, 0);
+            prop_assert_eq!(carried, lamports as u128 * math::SCALE + dust);
+        }
+    }
+}
+
+/// 256-bit reward math at the extremes of balance, supply and cumulative values
+mod wide_math_properties {
+    use super::*;
+
+    /// Reference owed calculation that splits the cum delta around SCALE so
+    /// every intermediate fits in u128
+    fn reference_owed(balance: u64, diff: u128) -> Option<u64> {
+        let (whole, frac) = (diff / math::SCALE, diff % math::SCALE);
+        let owed = (balance as u128)
+            .checked_mul(whole)?
+            .checked_add((balance as u128) * frac / math::SCALE)?;
+        u64::try_from(owed).ok()
+    }
+
+    proptest! {
+        /// Property: owed rewards match the reference for any balance and cum delta
+        #[test]
+        fn owed_matches_reference_everywhere(
+            balance in any::<u64>(),
+            last_cum in any::<u128>(),
+            diff in any::<u128>()
+        ) {
+            let cum = last_cum.saturating_add(diff);
+            let diff = cum - last_cum;
+            prop_assert_eq!(
+                math::calculate_owed_rewards(balance, cum, last_cum),
+                reference_owed(balance, diff)
+            );
+        }
+
+        /// Property: 9-decimal, multi-billion supply mints never overflow the product
+        #[test]
+        fn high_supply_high_decimals_never_overflow(
+            balance in 1_000_000_000_000_000_000u64..=10_000_000_000_000_000_000u64,
+            lamports in 0u64..=1_000_000_000_000_000_000u64
+        ) {
+            // One holder owning the entire 10B-token (1e19 base unit) supply
+            let supply = 10_000_000_000_000_000_000u64;
+            let (delta_cum, _) = math::reward_per_token_delta(lamports, supply, 0).unwrap();
+
+            let owed = math::calculate_owed_rewards(balance, delta_cum, 0);
+            prop_assert!(owed.is_some(), "Owed rewards must not overflow");
+            prop_assert!(owed.unwrap() <= lamports, "Holder cannot be owed more than was distributed");
+        }
+
+        /// Property: mul_div is exact floor division of the full product
+        #[test]
+        fn mul_div_matches_u256(a in any::<u128>(), b in any::<u128>(), denom in 1u128..) {
+            let expected = (math::U256::from(a) * math::U256::from(b)) / math::U256::from(denom);
+            match math::mul_div(a, b, denom) {
+                Some(result) => prop_assert_eq!(math::U256::from(result), expected),
+                None => prop_assert!(expected > math::U256::from(u128::MAX)),
+            }
+        }
+
+        /// Property: owed is monotonic in the cumulative value
+        #[test]
+        fn owed_monotonic_in_cum(
+            balance in any::<u64>(),
+            last_cum in any::<u128>(),
+            step1 in 0u128..u64::MAX as u128,
+            step2 in 0u128..u64::MAX as u128
+        ) {
+            let cum1 = last_cum.saturating_add(step1);
+            let cum2 = cum1.saturating_add(step2);
+            if let (Some(owed1), Some(owed2)) = (
+                math::calculate_owed_rewards(balance, cum1, last_cum),
+                math::calculate_owed_rewards(balance, cum2, last_cum),
+            ) {
+                prop_assert!(owed2 >= owed1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extreme_values() {
+        // Maximum balance times a cum delta that would overflow u128
+        let cum = u128::MAX / u64::MAX as u128 + 1;
+        assert!((u64::MAX as u128).checked_mul(cum).is_none());
+        assert_eq!(
+            math::calculate_owed_rewards(u64::MAX, cum, 0),
+            reference_owed(u64::MAX, cum)
+        );
+
+        // Results that cannot fit in u64 are rejected rather than truncated
+        assert_eq!(math::calculate_owed_rewards(u64::MAX, 2 * math::SCALE, 0), None);
+
+        // A cum value behind the user's checkpoint is an error
+        assert_eq!(math::calculate_owed_rewards(1, 0, 1), None);
+
+        // Division by zero is rejected
+        assert_eq!(math::mul_div(1, 1, 0), None);
+    }
+}
+
 /// Stress tests for performance and limits
 mod stress_tests {
     use super::*;
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
 use solana_tax_reward::{
-    state::{Config, GlobalState, UserInfo},
+    math, merkle, nft,
+    state::{
+        BatchClaimResult, BatchClaimStatus, Config, Epoch, GlobalState, LockPosition, LockTier,
+        PendingRewards, TaxRounding, TaxSchedule, TaxWindow, UserInfo,
+    },
     error::TaxRewardError,
 };
 use proptest::prelude::*;
@@ -77,6 +81,7 @@ fn test_state_serialization() {
         owner: Pubkey::new_unique(),
         dex_program: Pubkey::new_unique(),
         paused: false,
+        ..Default::default()
     };
     
     let serialized = config.try_to_vec().unwrap();
@@ -89,6 +94,7 @@ fn test_state_serialization() {
     let global_state = GlobalState {
         total_supply: 1_000_000,
         cum_reward_per_token: 123456789,
+        ..Default::default()
     };
     
     let serialized = global_state.try_to_vec().unwrap();
@@ -100,6 +106,7 @@ fn test_state_serialization() {
     let user_info = UserInfo {
         last_cum: 987654321,
         balance_snapshot: 5000,
+        ..Default::default()
     };
     
     let serialized = user_info.try_to_vec().unwrap();
@@ -111,9 +118,644 @@ fn test_state_serialization() {
 /// Test account size calculations
 #[test]
 fn test_account_sizes() {
-    assert_eq!(Config::LEN, 2 + 32 + 32 + 1); // u16 + Pubkey + Pubkey + bool
-    assert_eq!(GlobalState::LEN, 8 + 16); // u64 + u128
-    assert_eq!(UserInfo::LEN, 16 + 8); // u128 + u64
+    assert_eq!(Config::LEN, 2 + 32 + 32 + 1 + 32 + 2 + 1 + 8 + 8 + 32 + 8 + 1 + 10 * 4 + 2); // u16 + Pubkey + Pubkey + bool + Pubkey + u16 + TaxRounding + u64 + i64 + Pubkey + i64 + bool + [LockTier; 4] + u16
+    assert_eq!(GlobalState::LEN, 8 + 16 + 8 + 16 + 8 + 16 + 8 + 8 + 7 * 8 + 8 + 16); // u64 + u128 + i64 + u128 + u64 + u128 + i64 + i64 + 7 * u64 + u64 + u128
+    assert_eq!(UserInfo::LEN, 16 + 8 + 1 + 33 + 33 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8); // u128 + u64 + bool + Option<Pubkey> + Option<Pubkey> + i64 + u64 + u64 + u64 + u64 + u64 + u64 + i64 + u64
+    assert_eq!(LockPosition::LEN, 32 + 8 + 8 + 8 + 8 + 8); // Pubkey + u64 + u64 + u64 + i64 + i64
+}
+
+/// Test tax rounding policies and dust carry-over
+#[test]
+fn test_tax_rounding_policies() {
+    // 1234 * 500 / 10_000 = 61.7
+    assert_eq!(math::calculate_tax(1234, 500, TaxRounding::Floor, 0), Some((61, 7_000)));
+    assert_eq!(math::calculate_tax(1234, 500, TaxRounding::Ceil, 0), Some((62, -3_000)));
+    assert_eq!(math::calculate_tax(1234, 500, TaxRounding::Bankers, 0), Some((62, -3_000)));
+
+    // Exact halves round to even under banker's rounding
+    assert_eq!(math::calculate_tax(50, 500, TaxRounding::Bankers, 0), Some((2, 5_000))); // 2.5
+    assert_eq!(math::calculate_tax(70, 500, TaxRounding::Bankers, 0), Some((4, -5_000))); // 3.5
+
+    // Small trades pay zero with floor, but the dust eventually becomes tax
+    let mut carry = 0i64;
+    let mut collected = 0u64;
+    for _ in 0..10 {
+        let (tax, new_carry) = math::calculate_tax(10, 500, TaxRounding::Floor, carry).unwrap();
+        collected += tax;
+        carry = new_carry;
+    }
+    assert_eq!(collected, 5); // 10 trades * 0.5 tokens
+    assert_eq!(carry, 0);
+
+    // Tax is clamped to the trade amount and the excess stays carried
+    assert_eq!(math::calculate_tax(1, 10_000, TaxRounding::Floor, 9_999), Some((1, 9_999)));
+    assert_eq!(math::calculate_tax(1, 0, TaxRounding::Ceil, -9_999), Some((0, -9_999)));
+}
+
+/// Test tax holiday window selection and pruning
+#[test]
+fn test_tax_schedule_windows() {
+    let mut schedule = TaxSchedule {
+        windows: vec![
+            TaxWindow { start_ts: 100, end_ts: 200, tax_rate_bps: 0 },
+            TaxWindow { start_ts: 300, end_ts: 400, tax_rate_bps: 250 },
+        ],
+    };
+
+    assert_eq!(schedule.active_rate(99), None);
+    assert_eq!(schedule.active_rate(100), Some(0));
+    assert_eq!(schedule.active_rate(199), Some(0));
+    assert_eq!(schedule.active_rate(200), None); // end is exclusive
+    assert_eq!(schedule.active_rate(350), Some(250));
+
+    // Only the first window has ended at t=250
+    assert_eq!(schedule.prune_expired(250), 1);
+    assert_eq!(schedule.windows.len(), 1);
+    assert_eq!(schedule.windows[0].tax_rate_bps, 250);
+
+    // Full schedule fits in the allocated space
+    schedule.windows = vec![TaxWindow::default(); TaxSchedule::MAX_WINDOWS];
+    assert_eq!(schedule.try_to_vec().unwrap().len(), TaxSchedule::LEN);
+}
+
+/// Build a MetadataV1 account body with an optional collection
+fn craft_metadata(mint: Pubkey, collection: Option<(bool, Pubkey)>) -> Vec<u8> {
+    let mut data = vec![nft::METADATA_V1_KEY];
+    data.extend_from_slice(Pubkey::new_unique().as_ref()); // update_authority
+    data.extend_from_slice(mint.as_ref());
+    for field in ["Partner #1", "PTNR", "https://example.com/1.json"] {
+        data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        data.extend_from_slice(field.as_bytes());
+    }
+    data.extend_from_slice(&500u16.to_le_bytes()); // seller_fee_basis_points
+    data.push(1); // creators: Some(vec![creator])
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(&[1, 100]);
+    data.extend_from_slice(&[0, 1]); // primary_sale_happened, is_mutable
+    data.extend_from_slice(&[1, 255]); // edition_nonce
+    data.extend_from_slice(&[1, 0]); // token_standard
+    match collection {
+        Some((verified, key)) => {
+            data.push(1);
+            data.push(verified as u8);
+            data.extend_from_slice(key.as_ref());
+        }
+        None => data.push(0),
+    }
+    data.extend_from_slice(&[0u8; 64]); // uses, padding
+    data
+}
+
+/// Test Metaplex collection verification against crafted metadata
+#[test]
+fn test_nft_collection_metadata() {
+    let mint = Pubkey::new_unique();
+    let collection = Pubkey::new_unique();
+
+    let verified = nft::parse_collection_metadata(&craft_metadata(mint, Some((true, collection)))).unwrap();
+    assert_eq!(verified.mint, mint);
+    assert!(nft::is_verified_member(&verified, &collection));
+    assert!(!nft::is_verified_member(&verified, &Pubkey::new_unique()));
+
+    let unverified = nft::parse_collection_metadata(&craft_metadata(mint, Some((false, collection)))).unwrap();
+    assert!(!nft::is_verified_member(&unverified, &collection));
+
+    let no_collection = nft::parse_collection_metadata(&craft_metadata(mint, None)).unwrap();
+    assert!(!nft::is_verified_member(&no_collection, &collection));
+
+    // Wrong discriminator and truncated data are rejected
+    let mut wrong_key = craft_metadata(mint, Some((true, collection)));
+    wrong_key[0] = 0;
+    assert!(nft::parse_collection_metadata(&wrong_key).is_none());
+    assert!(nft::parse_collection_metadata(&wrong_key[..40]).is_none());
+}
+
+/// Test Merkle proofs for epoch claims against a locally built tree
+#[test]
+fn test_epoch_merkle_proofs() {
+    let wallets: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let amounts = [100u64, 250, 0, 1_000_000];
+    let leaves: Vec<[u8; 32]> = (0..4)
+        .map(|i| merkle::leaf_hash(i as u32, &wallets[i], amounts[i]))
+        .collect();
+    let left = merkle::node_hash(&leaves[0], &leaves[1]);
+    let right = merkle::node_hash(&leaves[2], &leaves[3]);
+    let root = merkle::node_hash(&left, &right);
+
+    // Every leaf verifies with its sibling path
+    assert!(merkle::verify(&[leaves[1], right], &root, leaves[0]));
+    assert!(merkle::verify(&[leaves[0], right], &root, leaves[1]));
+    assert!(merkle::verify(&[leaves[3], left], &root, leaves[2]));
+    assert!(merkle::verify(&[leaves[2], left], &root, leaves[3]));
+
+    // Tampered amount, wallet or index fails
+    let forged = merkle::leaf_hash(0, &wallets[0], amounts[0] + 1);
+    assert!(!merkle::verify(&[leaves[1], right], &root, forged));
+    let stolen = merkle::leaf_hash(0, &wallets[1], amounts[0]);
+    assert!(!merkle::verify(&[leaves[1], right], &root, stolen));
+    let reindexed = merkle::leaf_hash(1, &wallets[0], amounts[0]);
+    assert!(!merkle::verify(&[leaves[1], right], &root, reindexed));
+
+    // Truncated or empty proofs fail
+    assert!(!merkle::verify(&[right], &root, leaves[0]));
+    assert!(!merkle::verify(&[], &root, leaves[0]));
+}
+
+/// Test the epoch claimed-bitmap
+#[test]
+fn test_epoch_claimed_bitmap() {
+    assert_eq!(Epoch::bitmap_len(1), 1);
+    assert_eq!(Epoch::bitmap_len(8), 1);
+    assert_eq!(Epoch::bitmap_len(9), 2);
+    assert!(Epoch::space(Epoch::MAX_LEAVES) + 8 <= 10_240);
+
+    let mut epoch = Epoch {
+        epoch_id: 7,
+        merkle_root: [0; 32],
+        total_lamports: 1_000,
+        claimed_lamports: 0,
+        leaf_count: 20,
+        claimed_bitmap: vec![0; Epoch::bitmap_len(20)],
+    };
+    assert_eq!(epoch.try_to_vec().unwrap().len(), Epoch::space(20));
+
+    assert!(!epoch.is_claimed(9));
+    epoch.set_claimed(9);
+    assert!(epoch.is_claimed(9));
+    assert!(!epoch.is_claimed(8));
+    assert!(!epoch.is_claimed(10));
+    epoch.set_claimed(19);
+    assert!(epoch.is_claimed(19));
+}
+
+/// Test lifetime stats and zero-padded decoding of pre-stats accounts
+#[test]
+fn test_user_info_lifetime_stats() {
+    let mut user_info = UserInfo {
+        last_cum: 5,
+        balance_snapshot: 1_000,
+        claim_destination: Some(Pubkey::new_unique()),
+        ..Default::default()
+    };
+
+    // An account written before the stats fields existed, grown by the
+    // migration: the new tail is zero-filled and decodes to defaults
+    let mut data = user_info.try_to_vec().unwrap();
+    data.truncate(data.len() - 5 * 8);
+    data.resize(UserInfo::LEN, 0);
+    let migrated = UserInfo::deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(migrated.claim_destination, user_info.claim_destination);
+    assert_eq!(migrated.total_tax_paid, 0);
+    assert_eq!(migrated.first_interaction_ts, 0);
+
+    user_info.touch(100);
+    user_info.touch(250);
+    assert_eq!(user_info.first_interaction_ts, 100);
+    assert_eq!(user_info.last_interaction_ts, 250);
+
+    user_info.record_taxed_trade(50).unwrap();
+    user_info.record_taxed_trade(25).unwrap();
+    user_info.record_claim(1_000).unwrap();
+    assert_eq!(user_info.total_tax_paid, 75);
+    assert_eq!(user_info.taxed_trade_count, 2);
+    assert_eq!(user_info.total_claimed, 1_000);
+    assert_eq!(user_info.record_claim(u64::MAX), None);
+}
+
+/// Test protocol-wide counters
+#[test]
+fn test_global_protocol_stats() {
+    let mut global = GlobalState {
+        total_supply: 1_000_000,
+        cum_reward_per_token: 0,
+        ..Default::default()
+    };
+
+    global.record_swap(50, 2_000).unwrap();
+    global.record_swap(10, 500).unwrap();
+    assert_eq!(global.total_tokens_taxed, 60);
+    assert_eq!(global.total_sol_swapped, 2_500);
+    assert_eq!(global.swap_count, 2);
+    assert_eq!(global.largest_swap, 2_000);
+
+    // Zero payouts are not counted as claims
+    global.outstanding_liability = 1_000;
+    global.record_claim(0).unwrap();
+    global.record_claim(700).unwrap();
+    assert_eq!(global.claim_count, 1);
+    assert_eq!(global.total_sol_claimed, 700);
+    assert_eq!(global.outstanding_liability, 300);
+    // Payouts of deposits made before liability tracking saturate at zero
+    global.record_claim(500).unwrap();
+    assert_eq!(global.outstanding_liability, 0);
+    assert_eq!(global.claim_count, 2);
+
+    global.register_holder().unwrap();
+    assert_eq!(global.unique_holders, 1);
+
+    global.total_sol_swapped = u64::MAX;
+    assert_eq!(global.record_swap(0, 1), None);
+}
+
+/// Test a claim spanning a supply change pays each period at its own supply
+#[test]
+fn test_claim_spanning_supply_change() {
+    // 1_000 lamports over 100 tokens, then the supply doubles and another
+    // 1_000 lamports are spread over 200 tokens
+    let (delta_before, dust) = math::reward_per_token_delta(1_000, 100, 0).unwrap();
+    let checkpoint_cum = delta_before;
+    let (delta_after, _) = math::reward_per_token_delta(1_000, 200, dust).unwrap();
+    let cum = checkpoint_cum + delta_after;
+
+    // A 50-token holder earns 500 before the change and 250 after
+    assert_eq!(math::calculate_owed_rewards(50, checkpoint_cum, 0), Some(500));
+    assert_eq!(math::calculate_owed_rewards(50, cum, checkpoint_cum), Some(250));
+    assert_eq!(math::calculate_owed_rewards(50, cum, 0), Some(750));
+}
+
+/// Test streamed deposits: rate, leftover roll-over, dust and extension policy
+#[test]
+fn test_streaming_deposits() {
+    let scale = math::SCALE;
+    let mut global = GlobalState { eligible_supply: 1_000, ..Default::default() };
+
+    // Instant mode spreads the deposit right away
+    global.deposit(1_000, 0, 0).unwrap();
+    assert_eq!(global.cum_reward_per_token, scale);
+    assert_eq!(global.outstanding_liability, 1_000);
+
+    // An empty deposit touches nothing, not even the stream end
+    let mut global = GlobalState { eligible_supply: 1_000, ..Default::default() };
+    global.deposit(0, 100, 10).unwrap();
+    assert_eq!(global.period_finish, 0);
+    assert_eq!(global.last_update_time, 0);
+
+    // 1_000 lamports over 100s
+    global.deposit(1_000, 100, 0).unwrap();
+    assert_eq!(global.reward_rate, 10 * scale);
+    assert_eq!(global.period_finish, 100);
+    assert_eq!(global.reward_dust, 0);
+
+    // Half way through, half the deposit has been emitted
+    global.accrue(50).unwrap();
+    assert_eq!(global.cum_reward_per_token, scale / 2);
+
+    // A small deposit joins the running stream without extending it
+    let mut small = global.clone();
+    small.deposit(100, 100, 50).unwrap();
+    assert_eq!(small.period_finish, 100);
+    assert_eq!(small.reward_rate, 12 * scale);
+    for now in 51..60 {
+        small.deposit(1, 100, now).unwrap();
+    }
+    assert_eq!(small.period_finish, 100);
+
+    // A deposit at least as large as the leftover restarts the stream
+    // with the leftover rolled in
+    global.deposit(1_000, 100, 50).unwrap();
+    assert_eq!(global.period_finish, 150);
+    assert_eq!(global.reward_rate, 15 * scale);
+
+    // Accrual stops at the end of the stream
+    global.accrue(1_000).unwrap();
+    assert_eq!(global.cum_reward_per_token, 2 * scale);
+    assert_eq!(global.last_update_time, 1_000);
+
+    // The rate's division remainder is kept as dust
+    let mut global = GlobalState { eligible_supply: 1_000, ..Default::default() };
+    global.deposit(1_000, 3, 0).unwrap();
+    assert_eq!(global.reward_rate, 1_000 * scale / 3);
+    assert_eq!(global.reward_dust, 1_000 * scale % 3);
+    global.accrue(3).unwrap();
+    assert_eq!(global.cum_reward_per_token, scale);
+    assert_eq!(global.reward_dust, 0);
+}
+
+/// Test a stream of dust donations cannot delay the running reward stream
+#[test]
+fn test_dust_donations_do_not_delay_stream() {
+    let mut global = GlobalState { eligible_supply: 1_000, ..Default::default() };
+    global.deposit(10_000, 1_000, 0).unwrap();
+
+    // One-lamport donations every second, as a griefer would send them
+    for now in 1..=999 {
+        global.accrue(now).unwrap();
+        global.deposit(1, 1_000, now).unwrap();
+        assert_eq!(global.period_finish, 1_000);
+    }
+    global.accrue(1_000).unwrap();
+
+    // Everything deposited was emitted by the original end of the stream
+    let emitted = math::calculate_owed_rewards(1_000, global.cum_reward_per_token, 0).unwrap();
+    assert_eq!(emitted, 10_999);
+    assert_eq!(global.outstanding_liability, 10_999);
+}
+
+/// Test the reward weight across exclusion, threshold, staking and locks
+#[test]
+fn test_reward_weight() {
+    let mut cfg = Config::default();
+    let mut user_info = UserInfo { staked_amount: 300, ..Default::default() };
+    assert_eq!(cfg.reward_weight(&user_info, 1_000), 1_000);
+
+    // Staking mode weights the staked amount, not the wallet balance
+    cfg.staking_mode = true;
+    assert_eq!(cfg.reward_weight(&user_info, 1_000), 300);
+
+    // Boosted lock weight adds on top
+    user_info.locked_weight = 450;
+    assert_eq!(cfg.reward_weight(&user_info, 1_000), 750);
+
+    // Below the threshold nothing earns; at the threshold everything does
+    cfg.min_reward_balance = 751;
+    assert_eq!(cfg.reward_weight(&user_info, 1_000), 0);
+    cfg.min_reward_balance = 750;
+    assert_eq!(cfg.reward_weight(&user_info, 1_000), 750);
+
+    user_info.excluded = true;
+    assert_eq!(cfg.reward_weight(&user_info, 1_000), 0);
+
+    // Saturates rather than overflowing
+    user_info.excluded = false;
+    user_info.locked_weight = u64::MAX;
+    assert_eq!(cfg.reward_weight(&user_info, 1_000), u64::MAX);
+}
+
+/// Test snapshots keep eligible_supply equal to the sum of all snapshots
+#[test]
+fn test_eligible_supply_snapshots() {
+    fn resync(global: &mut GlobalState, cfg: &Config, user_info: &mut UserInfo, balance: u64) {
+        let weight = cfg.reward_weight(user_info, balance);
+        global.update_snapshot(user_info, weight).unwrap();
+    }
+    let mut cfg = Config { min_reward_balance: 100, ..Default::default() };
+    let mut global = GlobalState::default();
+    let mut alice = UserInfo::default();
+    let mut pool = UserInfo::default();
+
+    resync(&mut global, &cfg, &mut alice, 500);
+    resync(&mut global, &cfg, &mut pool, 1_500);
+    assert_eq!(global.eligible_supply, 2_000);
+
+    // Only eligible holders share a distribution
+    global.deposit(2_000, 0, 0).unwrap();
+    assert_eq!(math::calculate_owed_rewards(500, global.cum_reward_per_token, 0), Some(500));
+
+    // Excluding the pool re-weights it to zero
+    pool.excluded = true;
+    resync(&mut global, &cfg, &mut pool, 1_500);
+    assert_eq!(global.eligible_supply, 500);
+
+    // Dropping below the threshold removes the holder, crossing back re-adds
+    resync(&mut global, &cfg, &mut alice, 99);
+    assert_eq!((alice.balance_snapshot, global.eligible_supply), (0, 0));
+    resync(&mut global, &cfg, &mut alice, 100);
+    assert_eq!((alice.balance_snapshot, global.eligible_supply), (100, 100));
+
+    // Staking mode switches the weight to the staked amount
+    cfg.staking_mode = true;
+    alice.staked_amount = 250;
+    resync(&mut global, &cfg, &mut alice, 100);
+    assert_eq!(global.eligible_supply, 250);
+
+    // A snapshot larger than the eligible supply is rejected before any write
+    let mut stray = UserInfo { balance_snapshot: 1_000, ..Default::default() };
+    assert_eq!(global.eligible_supply_after(stray.balance_snapshot, 0), None);
+    assert_eq!(global.update_snapshot(&mut stray, 0), None);
+    assert_eq!((stray.balance_snapshot, global.eligible_supply), (1_000, 250));
+}
+
+/// Test settlement pays what the vault can cover and carries the rest
+#[test]
+fn test_settle_carries_rent_floor_shortfall() {
+    let cum = 3 * math::SCALE;
+    let mut user_info = UserInfo { balance_snapshot: 100, ..Default::default() };
+
+    // 300 owed, only 120 above the rent floor
+    assert_eq!(user_info.settle(cum, 120), Some((120, 300)));
+    assert_eq!(user_info.unpaid_rewards, 180);
+    assert_eq!(user_info.last_cum, cum);
+
+    // Nothing new accrued: the carry-over alone is due
+    assert_eq!(user_info.settle(cum, 0), Some((0, 180)));
+    assert_eq!(user_info.unpaid_rewards, 180);
+
+    // New rewards are added to the carry-over and paid in full once funded
+    assert_eq!(user_info.settle(4 * math::SCALE, 1_000), Some((280, 280)));
+    assert_eq!(user_info.unpaid_rewards, 0);
+    assert_eq!(user_info.settle(4 * math::SCALE, 1_000), Some((0, 0)));
+
+    // A stale accumulator is an error, not a silent zero
+    assert_eq!(user_info.settle(math::SCALE, 1_000), None);
+}
+
+/// Test accounts written by the pre-upgrade program decode once grown, and
+/// legacy snapshots are settled out rather than left outside eligible_supply
+#[test]
+fn test_legacy_layout_migration() {
+    #[derive(BorshSerialize)]
+    struct LegacyConfig {
+        tax_rate_bps: u16,
+        owner: Pubkey,
+        dex_program: Pubkey,
+        paused: bool,
+    }
+    #[derive(BorshSerialize)]
+    struct LegacyGlobalState {
+        total_supply: u64,
+        cum_reward_per_token: u128,
+    }
+    #[derive(BorshSerialize)]
+    struct LegacyUserInfo {
+        last_cum: u128,
+        balance_snapshot: u64,
+    }
+    // Anchor decodes from the front of the account and ignores trailing bytes
+    fn grow<T: BorshSerialize, U: BorshDeserialize>(legacy: &T, len: usize) -> U {
+        let mut data = legacy.try_to_vec().unwrap();
+        assert!(data.len() < len);
+        data.resize(len, 0);
+        U::deserialize(&mut data.as_slice()).unwrap()
+    }
+
+    let owner = Pubkey::new_unique();
+    let legacy_config = LegacyConfig {
+        tax_rate_bps: 500,
+        owner,
+        dex_program: Pubkey::new_unique(),
+        paused: false,
+    };
+    let config: Config = grow(&legacy_config, Config::LEN);
+    assert_eq!((config.tax_rate_bps, config.owner), (500, owner));
+    assert_eq!(config.tax_rounding, TaxRounding::Floor);
+    assert_eq!(config.reward_duration, 0);
+    assert!(!config.staking_mode);
+    assert!(config.lock_tiers.iter().all(|tier| !tier.is_enabled()));
+
+    let cum = 7 * math::SCALE;
+    let legacy_global = LegacyGlobalState { total_supply: 1_000_000, cum_reward_per_token: cum };
+    let mut global: GlobalState = grow(&legacy_global, GlobalState::LEN);
+    assert_eq!((global.total_supply, global.cum_reward_per_token), (1_000_000, cum));
+    assert_eq!((global.eligible_supply, global.reward_rate), (0, 0));
+
+    // migrate_state pins the accumulator legacy snapshots settle up to
+    global.legacy_cum = global.cum_reward_per_token;
+
+    // A: 500 tokens last settled at cum 5, so 1_000 lamports are still owed
+    let legacy_a = LegacyUserInfo { last_cum: 5 * math::SCALE, balance_snapshot: 500 };
+    let mut user_a: UserInfo = grow(&legacy_a, UserInfo::LEN);
+    assert_eq!(user_a.claim_destination, None);
+    user_a.retire_legacy_snapshot(global.legacy_cum, global.cum_reward_per_token).unwrap();
+    assert_eq!(user_a.unpaid_rewards, 1_000);
+    assert_eq!((user_a.balance_snapshot, user_a.last_cum), (0, cum));
+
+    // The zeroed snapshot can now be re-registered without underflowing
+    global.update_snapshot(&mut user_a, 500).unwrap();
+    assert_eq!(global.eligible_supply, 500);
+
+    // A deposit lands while only A is registered
+    global.deposit(100, 0, 0).unwrap();
+    let cum_after = global.cum_reward_per_token;
+
+    // B, legacy and fully settled at the upgrade, migrates afterwards: the
+    // deposit was spread over A alone, so B is owed nothing from it
+    let legacy_b = LegacyUserInfo { last_cum: cum, balance_snapshot: 500 };
+    let mut user_b: UserInfo = grow(&legacy_b, UserInfo::LEN);
+    user_b.retire_legacy_snapshot(global.legacy_cum, cum_after).unwrap();
+    assert_eq!(user_b.unpaid_rewards, 0);
+    assert_eq!(user_b.last_cum, cum_after);
+    global.update_snapshot(&mut user_b, 500).unwrap();
+
+    // Credited rewards match what was deposited after the upgrade
+    assert_eq!(user_a.settle(cum_after, u64::MAX), Some((1_100, 1_100)));
+    assert_eq!(user_b.settle(cum_after, u64::MAX), Some((0, 0)));
+}
+
+/// Test the holder bookkeeping done by mint_with_tracking and burn_with_tracking
+#[test]
+fn test_mint_and_burn_tracking() {
+    let cfg = Config::default();
+    let mut global = GlobalState::default();
+    let expiry = 30 * 86_400;
+
+    // Minting to a new recipient: snapshot and expiry clock both move
+    let mut recipient = UserInfo::default();
+    assert!(recipient.is_stale(10_000_000, expiry));
+    assert!(recipient.touch(10_000_000));
+    global.register_holder().unwrap();
+    let weight = cfg.reward_weight(&recipient, 400);
+    global.update_snapshot(&mut recipient, weight).unwrap();
+    assert_eq!((recipient.balance_snapshot, global.eligible_supply), (400, 400));
+    assert_eq!(global.unique_holders, 1);
+    assert!(!recipient.is_stale(10_000_000 + expiry - 1, expiry));
+
+    // Burning shrinks the burner's weight
+    let weight = cfg.reward_weight(&recipient, 250);
+    global.update_snapshot(&mut recipient, weight).unwrap();
+    assert_eq!(global.eligible_supply, 250);
+}
+
+/// Test lock boost weights and early-unlock penalties
+#[test]
+fn test_lock_boost_and_penalty() {
+    let tier = LockTier { duration_secs: 90 * 86_400, multiplier_bps: 15_000 };
+    assert!(tier.is_enabled());
+    assert!(!LockTier::default().is_enabled());
+
+    // 1.5x boost on 1_000 tokens
+    let weight = math::apply_bps(1_000, tier.multiplier_bps).unwrap();
+    assert_eq!(weight, 1_500);
+    // Boosted weight may not overflow u64
+    assert_eq!(math::apply_bps(u64::MAX, 20_000), None);
+    assert_eq!(math::apply_bps(u64::MAX, 10_000), Some(u64::MAX));
+
+    let position = LockPosition {
+        owner: Pubkey::new_unique(),
+        lock_id: 1,
+        amount: 1_000,
+        weight,
+        start_ts: 0,
+        unlock_ts: tier.duration_secs,
+    };
+    assert!(!position.is_unlocked(tier.duration_secs - 1));
+    assert!(position.is_unlocked(tier.duration_secs));
+
+    // 10% penalty rounds down in the user's favour
+    assert_eq!(math::apply_bps(position.amount, 1_000), Some(100));
+    assert_eq!(math::apply_bps(999, 1_000), Some(99));
+
+    // The penalty's proceeds go to the holders that remain: the unlocker's
+    // lock weight is removed before they are deposited
+    let cfg = Config { staking_mode: true, ..Default::default() };
+    let mut global = GlobalState::default();
+    let mut unlocker = UserInfo { locked_weight: weight, ..Default::default() };
+    let mut staker = UserInfo { staked_amount: 500, ..Default::default() };
+    for holder in [&mut unlocker, &mut staker] {
+        let weight = cfg.reward_weight(holder, 0);
+        global.update_snapshot(holder, weight).unwrap();
+    }
+    assert_eq!(global.eligible_supply, 2_000);
+
+    unlocker.locked_weight -= weight;
+    let weight = cfg.reward_weight(&unlocker, 0);
+    global.update_snapshot(&mut unlocker, weight).unwrap();
+    global.deposit(100, 0, 0).unwrap();
+    let cum = global.cum_reward_per_token;
+    assert_eq!(unlocker.settle(cum, u64::MAX), Some((0, 0)));
+    assert_eq!(staker.settle(cum, u64::MAX), Some((100, 100)));
+}
+
+/// Test reward expiry staleness boundaries
+#[test]
+fn test_reward_expiry_staleness() {
+    let mut user_info = UserInfo {
+        last_cum: 0,
+        balance_snapshot: 1_000,
+        last_interaction_ts: 1_000,
+        ..Default::default()
+    };
+
+    // Expiry disabled
+    assert!(!user_info.is_stale(i64::MAX, 0));
+
+    assert!(!user_info.is_stale(1_000 + 59, 60));
+    assert!(user_info.is_stale(1_000 + 60, 60));
+
+    // Interaction resets the clock
+    user_info.last_interaction_ts = 1_060;
+    assert!(!user_info.is_stale(1_060, 60));
+    assert!(!user_info.is_stale(i64::MIN, 60));
+}
+
+/// Test batch claim return data fits the 1024-byte limit for a full transaction
+#[test]
+fn test_batch_claim_return_data() {
+    let results = vec![
+        BatchClaimResult { status: BatchClaimStatus::Paid, amount: 1_500 },
+        BatchClaimResult::skipped(BatchClaimStatus::InvalidAccounts),
+        BatchClaimResult::skipped(BatchClaimStatus::DestinationMismatch),
+    ];
+    let data = results.try_to_vec().unwrap();
+    assert_eq!(data.len(), 4 + 3 * 9);
+    assert_eq!(Vec::<BatchClaimResult>::try_from_slice(&data).unwrap(), results);
+
+    // 64 accounts per transaction leave room for at most 21 triples
+    assert!(4 + 21 * 9 <= 1024);
+}
+
+/// Test pending rewards return data round-trips for clients
+#[test]
+fn test_pending_rewards_return_data() {
+    let pending = PendingRewards {
+        owed: 42_000,
+        balance_snapshot: 1_000,
+        last_claim_slot: 123_456,
+        token_balance: 1_250,
+    };
+    let data = pending.try_to_vec().unwrap();
+    assert_eq!(data.len(), 8 * 4);
+    assert_eq!(PendingRewards::try_from_slice(&data).unwrap(), pending);
 }
 
 /// Test overflow protection in calculations
@@ -163,6 +805,32 @@ proptest! {
         }
     }
     
+    /// Property: Tax collected over a sequence of trades matches the nominal rate
+    #[test]
+    fn prop_tax_dust_tracks_nominal_rate(
+        amounts in proptest::collection::vec(0u64..100_000u64, 1..50),
+        tax_rate_bps in 0u16..=10_000u16,
+        rounding in prop_oneof![
+            Just(TaxRounding::Floor),
+            Just(TaxRounding::Ceil),
+            Just(TaxRounding::Bankers),
+        ]
+    ) {
+        let mut carry = 0i64;
+        let mut collected = 0i128;
+        let mut nominal = 0i128;
+        for amount in amounts {
+            let (tax, new_carry) = math::calculate_tax(amount, tax_rate_bps, rounding, carry).unwrap();
+            prop_assert!(tax <= amount);
+            prop_assert!(new_carry.abs() < 10_000);
+            collected += tax as i128;
+            nominal += amount as i128 * tax_rate_bps as i128;
+            carry = new_carry;
+        }
+        // Collected tax plus outstanding dust is exactly the nominal tax
+        prop_assert_eq!(collected * 10_000 + carry as i128, nominal);
+    }
+
     /// Property: Reward calculation should be deterministic
     #[test]
     fn prop_reward_calculation_deterministic(
@@ -201,4 +869,7 @@ fn test_error_types() {
     
     let error = TaxRewardError::ProgramPaused;
     assert_eq!(error.to_string(), "Program is Paused");
+
+    let error = TaxRewardError::MemoTooLong;
+    assert_eq!(error.to_string(), "Memo Too Long");
 }